@@ -1,9 +1,14 @@
 fn main() {
     if cfg!(feature = "internal-regenerate") {
         let schema_path = "schemafy_lib/src/schema.json";
-        schemafy_lib::Generator::builder()
+        let mut builder = schemafy_lib::Generator::builder()
             .with_root_name_str("Schema")
-            .with_input_file(schema_path)
+            .with_input_file(schema_path);
+        // The `Schema` type must not depend on the format features enabled
+        for format in &["regex", "uri", "uri-reference"] {
+            builder = builder.with_format(format, "String");
+        }
+        builder
             .build()
             .generate_to_file("schemafy_lib/src/schema.rs")
             .unwrap();
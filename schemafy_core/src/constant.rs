@@ -0,0 +1,81 @@
+//! Support for the zero-sized types generated for schemas which allow a
+//! single value, through `const` or a single-element `enum`.
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// A type whose only value stands for the JSON value [`JSON`](Constant::JSON).
+pub trait Constant: Default {
+    /// The JSON text of the value.
+    const JSON: &'static str;
+
+    /// The value as JSON.
+    fn value() -> Value {
+        serde_json::from_str(Self::JSON).expect("Constant::JSON is valid JSON")
+    }
+}
+
+/// Serializes the value of the constant `T`.
+pub fn serialize<T, S>(serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Constant,
+    S: Serializer,
+{
+    T::value().serialize(serializer)
+}
+
+/// Deserializes the value of the constant `T`, rejecting any other value.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Constant,
+    D: Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    if value == T::value() {
+        Ok(T::default())
+    } else {
+        Err(de::Error::custom(format!(
+            "expected `{}`, got `{}`",
+            T::JSON,
+            value
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::from_str;
+
+    #[derive(Debug, Default, PartialEq)]
+    struct Request;
+
+    impl Constant for Request {
+        const JSON: &'static str = r#""request""#;
+    }
+
+    impl Serialize for Request {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serialize::<Self, S>(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Request {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize(deserializer)
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(serde_json::to_string(&Request).unwrap(), r#""request""#);
+        assert_eq!(from_str::<Request>(r#""request""#).unwrap(), Request);
+    }
+
+    #[test]
+    fn reject_other_values() {
+        let err = from_str::<Request>(r#""response""#).unwrap_err();
+        assert_eq!(err.to_string(), r#"expected `"request"`, got `"response"`"#);
+    }
+}
@@ -0,0 +1,279 @@
+//! Types for strings of the `format`s `email`, `hostname` and `byte`, which
+//! can only be deserialized from strings of that format.
+
+use std::{convert::TryFrom, fmt, ops::Deref, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// A string which is not of the expected `format`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatError {
+    format: &'static str,
+    value: String,
+}
+
+impl FormatError {
+    fn new(format: &'static str, value: &str) -> Self {
+        FormatError {
+            format,
+            value: value.to_owned(),
+        }
+    }
+
+    /// The expected `format`, such as `email`.
+    pub fn format(&self) -> &'static str {
+        self.format
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a valid {}", self.value, self.format)
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+macro_rules! string_format {
+    ($(#[$meta:meta])* $name:ident, $format:expr, $is_valid:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl TryFrom<String> for $name {
+            type Error = FormatError;
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                if $is_valid(&value) {
+                    Ok($name(value))
+                } else {
+                    Err(FormatError::new($format, &value))
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = FormatError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                $name::try_from(s.to_owned())
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+string_format!(
+    /// An email address, `local-part@domain`.
+    Email,
+    "email",
+    is_email
+);
+
+string_format!(
+    /// A host name as defined by RFC 1123.
+    Hostname,
+    "hostname",
+    is_hostname
+);
+
+fn is_email(s: &str) -> bool {
+    match s.rfind('@') {
+        Some(at) => {
+            let local = &s[..at];
+            !local.is_empty()
+                && !local.chars().any(|c| c.is_whitespace() || c.is_control())
+                && is_hostname(&s[at + 1..])
+        }
+        None => false,
+    }
+}
+
+fn is_hostname(s: &str) -> bool {
+    // A trailing dot marks a fully qualified name
+    let s = s.strip_suffix('.').unwrap_or(s);
+    !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Bytes, represented in JSON as a base64 string (the `byte` format), with
+/// padding.
+#[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Base64(pub Vec<u8>);
+
+impl Base64 {
+    /// Encodes the bytes as base64.
+    pub fn encode(&self) -> String {
+        let mut encoded = String::with_capacity((self.0.len() + 2) / 3 * 4);
+        for chunk in self.0.chunks(3) {
+            let bits = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |bits, (i, &b)| bits | u32::from(b) << (16 - 8 * i));
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    let index = (bits >> (18 - 6 * i)) & 0x3f;
+                    encoded.push(BASE64_ALPHABET[index as usize] as char);
+                } else {
+                    encoded.push('=');
+                }
+            }
+        }
+        encoded
+    }
+
+    /// Decodes a base64 string, which may leave out the padding.
+    pub fn decode(s: &str) -> Result<Self, FormatError> {
+        let error = || FormatError::new("byte", s);
+        let data = s.trim_end_matches('=');
+        if s.len() - data.len() > 2 || (s.len() != data.len() && s.len() % 4 != 0) {
+            return Err(error());
+        }
+        let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+        for chunk in data.as_bytes().chunks(4) {
+            if chunk.len() == 1 {
+                return Err(error());
+            }
+            let mut bits = 0u32;
+            for (i, &c) in chunk.iter().enumerate() {
+                let index = BASE64_ALPHABET
+                    .iter()
+                    .position(|&a| a == c)
+                    .ok_or_else(error)?;
+                bits |= (index as u32) << (18 - 6 * i);
+            }
+            for i in 0..chunk.len() - 1 {
+                bytes.push((bits >> (16 - 8 * i)) as u8);
+            }
+        }
+        Ok(Base64(bytes))
+    }
+}
+
+impl TryFrom<String> for Base64 {
+    type Error = FormatError;
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Base64::decode(&value)
+    }
+}
+
+impl FromStr for Base64 {
+    type Err = FormatError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Base64::decode(s)
+    }
+}
+
+impl From<Base64> for String {
+    fn from(value: Base64) -> Self {
+        value.encode()
+    }
+}
+
+impl From<Vec<u8>> for Base64 {
+    fn from(bytes: Vec<u8>) -> Self {
+        Base64(bytes)
+    }
+}
+
+impl From<Base64> for Vec<u8> {
+    fn from(value: Base64) -> Self {
+        value.0
+    }
+}
+
+impl Deref for Base64 {
+    type Target = Vec<u8>;
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::from_str;
+
+    #[test]
+    fn emails() {
+        assert_eq!(
+            &*"a.b@example.com".parse::<Email>().unwrap(),
+            "a.b@example.com"
+        );
+        assert!("a@b".parse::<Email>().is_ok());
+        for invalid in &["", "example.com", "@example.com", "a b@example.com", "a@-b"] {
+            assert!(invalid.parse::<Email>().is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            from_str::<Email>(r#""nope""#).unwrap_err().to_string(),
+            r#""nope" is not a valid email"#
+        );
+    }
+
+    #[test]
+    fn hostnames() {
+        for valid in &["localhost", "example.com.", "a-1.example.com"] {
+            assert!(valid.parse::<Hostname>().is_ok(), "{}", valid);
+        }
+        let long_label = "a".repeat(64);
+        for invalid in &["", "-a.com", "a..com", "a_b.com", &long_label[..]] {
+            assert!(invalid.parse::<Hostname>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn base64_round_trip() {
+        let cases: &[(&[u8], &str)] = &[
+            (b"", ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+            (b"\xff\xfe", "//4="),
+        ];
+        for &(bytes, encoded) in cases {
+            assert_eq!(Base64(bytes.to_vec()).encode(), encoded);
+            assert_eq!(Base64::decode(encoded).unwrap().0, bytes);
+        }
+        assert_eq!(Base64::decode("Zm8").unwrap().0, b"fo");
+        for invalid in &["Z", "Zm9v!", "Z===", "Zg="] {
+            assert!(Base64::decode(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            serde_json::to_string(&Base64(b"foo".to_vec())).unwrap(),
+            r#""Zm9v""#
+        );
+        assert_eq!(from_str::<Base64>(r#""Zm9v""#).unwrap().0, b"foo");
+    }
+}
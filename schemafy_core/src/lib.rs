@@ -1 +1,18 @@
-pub mod one_or_many;
+pub mod constant;
+pub mod format;
+pub mod number;
+pub mod one_or_many;
+pub mod tagged;
+#[cfg(feature = "validate")]
+pub mod validate;
+
+#[doc(hidden)]
+pub use serde;
+
+// The types of the string formats which `schemafy` maps to other crates
+#[cfg(feature = "chrono")]
+pub use chrono;
+#[cfg(feature = "url")]
+pub use url;
+#[cfg(feature = "uuid")]
+pub use uuid;
@@ -0,0 +1,193 @@
+//! Numbers which do not fit the primitive types: numbers encoded as JSON
+//! strings, such as `{"type": "string", "format": "int64"}`, and decimal
+//! numbers kept exactly as written.
+//!
+//! JSON numbers only keep all of their digits through serde_json if its
+//! `arbitrary_precision` feature is enabled, which the feature of the same
+//! name of this crate does. `serde_json::Number` then holds any number.
+
+use std::{fmt, ops::Deref, str::FromStr};
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+/// A value which is represented in JSON as a string, such as a 64 bit
+/// integer which some JSON parsers could not hold as a number.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StringEncoded<T>(pub T);
+
+impl<T: fmt::Display> Serialize for StringEncoded<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&self.0)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for StringEncoded<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map(StringEncoded)
+            .map_err(|err| de::Error::custom(format!("invalid number {:?}: {}", s, err)))
+    }
+}
+
+impl<T> From<T> for StringEncoded<T> {
+    fn from(value: T) -> Self {
+        StringEncoded(value)
+    }
+}
+
+impl<T> Deref for StringEncoded<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for StringEncoded<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: FromStr> FromStr for StringEncoded<T> {
+    type Err = T::Err;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(StringEncoded)
+    }
+}
+
+/// A decimal number, such as an amount of money, kept exactly as written.
+///
+/// It is a JSON number, use `StringEncoded<Decimal>` for decimal numbers
+/// encoded as strings. All of its digits are only kept with the
+/// `arbitrary_precision` feature, otherwise they go through an `f64`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Decimal(String);
+
+impl Decimal {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The nearest `f64` to the number.
+    pub fn to_f64(&self) -> f64 {
+        self.0.parse().expect("Decimal is a valid f64")
+    }
+}
+
+/// A string which is not a decimal number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseDecimalError(String);
+
+impl fmt::Display for ParseDecimalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is not a decimal number", self.0)
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    /// Parses a number in the syntax of JSON numbers, such as `-12.50` or
+    /// `1e100`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        fn digits(s: &str) -> &str {
+            s.trim_start_matches(|c: char| c.is_ascii_digit())
+        }
+        let unsigned = s.strip_prefix('-').unwrap_or(s);
+        let rest = digits(unsigned);
+        let integer = &unsigned[..unsigned.len() - rest.len()];
+        let mut valid = !integer.is_empty() && (integer == "0" || !integer.starts_with('0'));
+        let rest = match rest.strip_prefix('.') {
+            Some(fraction) => {
+                let rest = digits(fraction);
+                valid &= rest.len() < fraction.len();
+                rest
+            }
+            None => rest,
+        };
+        let rest = match rest.strip_prefix(['e', 'E']) {
+            Some(exponent) => {
+                let exponent = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+                let rest = digits(exponent);
+                valid &= rest.len() < exponent.len();
+                rest
+            }
+            None => rest,
+        };
+        if valid && rest.is_empty() {
+            Ok(Decimal(s.to_owned()))
+        } else {
+            Err(ParseDecimalError(s.to_owned()))
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_json::Number::from_str(&self.0)
+            .map_err(ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let number = serde_json::Number::deserialize(deserializer)?;
+        Ok(Decimal(number.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{from_str, to_string};
+
+    #[test]
+    fn string_encoded_round_trip() {
+        let id: StringEncoded<i64> = from_str(r#""-9007199254740993""#).unwrap();
+        assert_eq!(*id, -9007199254740993);
+        assert_eq!(to_string(&id).unwrap(), r#""-9007199254740993""#);
+        assert_eq!(
+            from_str::<StringEncoded<u8>>(r#""256""#)
+                .unwrap_err()
+                .to_string(),
+            r#"invalid number "256": number too large to fit in target type"#
+        );
+        from_str::<StringEncoded<i64>>("1").unwrap_err();
+    }
+
+    #[test]
+    fn parse_decimal() {
+        for valid in &["0", "-12.50", "1e100", "2.5E-3", "0.1"] {
+            assert_eq!(valid.parse::<Decimal>().unwrap().as_str(), *valid);
+        }
+        for invalid in &["", "-", "1.", ".5", "1e", "1.2.3", "0x10", "1 ", "012"] {
+            assert!(invalid.parse::<Decimal>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        let amount: Decimal = from_str("12.5").unwrap();
+        assert_eq!(amount.to_f64(), 12.5);
+        assert_eq!(to_string(&amount).unwrap(), "12.5");
+        let amount: StringEncoded<Decimal> = from_str(r#""12.50""#).unwrap();
+        assert_eq!(amount.as_str(), "12.50");
+        assert_eq!(to_string(&amount).unwrap(), r#""12.50""#);
+        from_str::<Decimal>(r#""12.5""#).unwrap_err();
+    }
+}
@@ -0,0 +1,201 @@
+//! Support for the enums generated for a `oneOf` whose schemas are told apart
+//! by a property, the tag.
+//!
+//! The variants of referenced schemas hold the type generated for the schema,
+//! which keeps the tag as one of its fields. The variants of the other schemas
+//! hold their other properties, or nothing if the tag is the only one.
+
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// An object read for a tagged enum, together with the value of its tag.
+#[derive(Clone, Debug)]
+pub struct Tagged {
+    tag: &'static str,
+    value: String,
+    object: Map<String, Value>,
+}
+
+impl Tagged {
+    /// Reads an object whose string property `tag` selects the variant.
+    pub fn deserialize<'de, D>(deserializer: D, tag: &'static str) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let object = Map::deserialize(deserializer)?;
+        let value = match object.get(tag) {
+            Some(Value::String(value)) => value.clone(),
+            Some(value) => {
+                return Err(de::Error::invalid_type(
+                    unexpected(value),
+                    &"a string tag",
+                ))
+            }
+            None => return Err(de::Error::missing_field(tag)),
+        };
+        Ok(Tagged { tag, value, object })
+    }
+
+    /// The value of the tag.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Deserializes the whole object, tag included, as the type of a
+    /// referenced schema.
+    pub fn with_tag<T, E>(self) -> Result<T, E>
+    where
+        T: de::DeserializeOwned,
+        E: de::Error,
+    {
+        serde_json::from_value(Value::Object(self.object)).map_err(E::custom)
+    }
+
+    /// Deserializes the other properties of the object.
+    pub fn without_tag<T, E>(mut self) -> Result<T, E>
+    where
+        T: de::DeserializeOwned,
+        E: de::Error,
+    {
+        self.object.remove(self.tag);
+        serde_json::from_value(Value::Object(self.object)).map_err(E::custom)
+    }
+
+    /// The error for a tag which selects none of the `variants`.
+    pub fn unknown_variant<E>(&self, variants: &'static [&'static str]) -> E
+    where
+        E: de::Error,
+    {
+        E::unknown_variant(&self.value, variants)
+    }
+}
+
+/// Serializes the object `value` with the property `tag` set to `tag_value`.
+pub fn serialize_with_tag<T, S>(
+    value: &T,
+    tag: &str,
+    tag_value: &str,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    let mut object = Map::new();
+    object.insert(tag.to_owned(), tag_value.into());
+    match serde_json::to_value(value).map_err(ser::Error::custom)? {
+        Value::Object(properties) => object.extend(properties),
+        value => {
+            return Err(ser::Error::custom(format!(
+                "expected an object, got `{}`",
+                value
+            )))
+        }
+    }
+    object.serialize(serializer)
+}
+
+/// Serializes an object whose only property is `tag`, set to `tag_value`.
+pub fn serialize_tag<S>(tag: &str, tag_value: &str, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serialize_with_tag(&Map::new(), tag, tag_value, serializer)
+}
+
+fn unexpected(value: &Value) -> de::Unexpected<'_> {
+    match value {
+        Value::Null => de::Unexpected::Unit,
+        Value::Bool(b) => de::Unexpected::Bool(*b),
+        Value::Number(_) => de::Unexpected::Other("number"),
+        Value::String(s) => de::Unexpected::Str(s),
+        Value::Array(_) => de::Unexpected::Seq,
+        Value::Object(_) => de::Unexpected::Map,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{from_str, json};
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Cat {
+        #[serde(rename = "petType")]
+        pet_type: String,
+        name: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Dog {
+        bark: bool,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Pet {
+        Cat(Cat),
+        Dog(Dog),
+        Fish,
+    }
+
+    impl Serialize for Pet {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Pet::Cat(value) => value.serialize(serializer),
+                Pet::Dog(value) => serialize_with_tag(value, "petType", "dog", serializer),
+                Pet::Fish => serialize_tag("petType", "fish", serializer),
+            }
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Pet {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let tagged = Tagged::deserialize(deserializer, "petType")?;
+            match tagged.value() {
+                "cat" | "kitten" => tagged.with_tag().map(Pet::Cat),
+                "dog" => tagged.without_tag().map(Pet::Dog),
+                "fish" => Ok(Pet::Fish),
+                _ => Err(tagged.unknown_variant(&["cat", "kitten", "dog", "fish"])),
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let cat = Pet::Cat(Cat {
+            pet_type: "kitten".into(),
+            name: "Tom".into(),
+        });
+        let json = json!({ "petType": "kitten", "name": "Tom" });
+        assert_eq!(serde_json::to_value(&cat).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Pet>(json).unwrap(), cat);
+
+        let dog = Pet::Dog(Dog { bark: true });
+        let json = json!({ "petType": "dog", "bark": true });
+        assert_eq!(serde_json::to_value(&dog).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Pet>(json).unwrap(), dog);
+
+        let json = json!({ "petType": "fish" });
+        assert_eq!(serde_json::to_value(&Pet::Fish).unwrap(), json);
+        assert_eq!(serde_json::from_value::<Pet>(json).unwrap(), Pet::Fish);
+    }
+
+    #[test]
+    fn reject_invalid_tags() {
+        let err = from_str::<Pet>(r#"{"name": "Tom"}"#).unwrap_err();
+        assert_eq!(err.to_string(), "missing field `petType`");
+
+        let err = from_str::<Pet>(r#"{"petType": 1}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid type: number, expected a string tag"
+        );
+
+        let err = from_str::<Pet>(r#"{"petType": "bird"}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown variant `bird`, expected one of `cat`, `kitten`, `dog`, `fish`"
+        );
+    }
+}
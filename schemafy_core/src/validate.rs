@@ -0,0 +1,635 @@
+//! Checks of the constraints which serde does not enforce while
+//! deserializing, such as `minimum` or `pattern`, for the types generated
+//! with validation enabled.
+//!
+//! Generated types implement [`Validate`] and report every violated
+//! constraint, located by the JSON pointer of the offending value.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fmt,
+};
+
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer};
+
+/// A value which violates a constraint of its schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    path: String,
+    keyword: &'static str,
+    message: String,
+}
+
+impl ValidationError {
+    pub fn new(path: &str, keyword: &'static str, message: impl Into<String>) -> Self {
+        ValidationError {
+            path: path.to_owned(),
+            keyword,
+            message: message.into(),
+        }
+    }
+
+    /// The JSON pointer to the value, empty for the validated value itself.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The schema keyword of the violated constraint, such as `maxLength`.
+    pub fn keyword(&self) -> &'static str {
+        self.keyword
+    }
+
+    /// The description of the violation, without its location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// All of the constraints violated by a value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidationErrors(Vec<ValidationError>);
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        ValidationErrors::default()
+    }
+
+    pub fn push(&mut self, error: ValidationError) {
+        self.0.push(error);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, ValidationError> {
+        self.0.iter()
+    }
+
+    /// `Ok` if no constraint is violated.
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl IntoIterator for ValidationErrors {
+    type Item = ValidationError;
+    type IntoIter = std::vec::IntoIter<ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ValidationErrors {
+    type Item = &'a ValidationError;
+    type IntoIter = std::slice::Iter<'a, ValidationError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// A value whose constraints can be checked.
+pub trait Validate {
+    /// Checks `self`, found at the JSON pointer `path`, and everything within
+    /// it, adding the violated constraints to `errors`.
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors);
+
+    /// Checks `self` and everything within it.
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        self.validate_into("", &mut errors);
+        errors.into_result()
+    }
+}
+
+macro_rules! unconstrained {
+    ($($t:ty),*) => {
+        $(
+            impl Validate for $t {
+                fn validate_into(&self, _: &str, _: &mut ValidationErrors) {}
+            }
+        )*
+    };
+}
+
+unconstrained!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    f32,
+    f64,
+    String,
+    serde_json::Value,
+    std::net::IpAddr,
+    std::net::Ipv4Addr,
+    std::net::Ipv6Addr,
+    crate::format::Email,
+    crate::format::Hostname,
+    crate::format::Base64,
+    crate::number::Decimal
+);
+
+impl<T> Validate for crate::number::StringEncoded<T> {
+    fn validate_into(&self, _: &str, _: &mut ValidationErrors) {}
+}
+
+impl<T: Validate + ?Sized> Validate for Box<T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        (**self).validate_into(path, errors)
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        if let Some(value) = self {
+            value.validate_into(path, errors)
+        }
+    }
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        for (i, value) in self.iter().enumerate() {
+            value.validate_into(&join(path, &i.to_string()), errors);
+        }
+    }
+}
+
+impl<T: Validate> Validate for BTreeMap<String, T> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        for (key, value) in self {
+            value.validate_into(&join(path, key), errors);
+        }
+    }
+}
+
+impl<T: Validate, S> Validate for HashMap<String, T, S> {
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+        for (key, value) in self {
+            value.validate_into(&join(path, key), errors);
+        }
+    }
+}
+
+macro_rules! tuple {
+    ($($t:ident $i:tt),*) => {
+        impl<$($t: Validate),*> Validate for ($($t,)*) {
+            fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+                $( self.$i.validate_into(&join(path, stringify!($i)), errors); )*
+            }
+        }
+    };
+}
+
+tuple!(A 0);
+tuple!(A 0, B 1);
+tuple!(A 0, B 1, C 2);
+tuple!(A 0, B 1, C 2, D 3);
+tuple!(A 0, B 1, C 2, D 3, E 4);
+tuple!(A 0, B 1, C 2, D 3, E 4, F 5);
+tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+tuple!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+/// Validates the value of a field of a generated type if the type of the
+/// field implements [`Validate`], and does nothing otherwise. Types which a
+/// string `format` is mapped to, such as `uuid::Uuid`, may well not.
+///
+/// Method resolution prefers [`ValidateField`], implemented for `&Field`,
+/// and falls back to [`SkipField`] only if the bound of the former does not
+/// hold.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __validate_field {
+    ($value:expr, $path:expr, $errors:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::validate::{SkipField as _, ValidateField as _};
+        (&&$crate::validate::Field($value)).validate_field($path, $errors)
+    }};
+}
+
+#[doc(hidden)]
+pub use crate::__validate_field as validate_field;
+
+#[doc(hidden)]
+pub struct Field<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait ValidateField {
+    fn validate_field(&self, path: &str, errors: &mut ValidationErrors);
+}
+
+impl<T: Validate + ?Sized> ValidateField for &Field<'_, T> {
+    fn validate_field(&self, path: &str, errors: &mut ValidationErrors) {
+        self.0.validate_into(path, errors)
+    }
+}
+
+#[doc(hidden)]
+pub trait SkipField {
+    fn validate_field(&self, path: &str, errors: &mut ValidationErrors);
+}
+
+impl<T: ?Sized> SkipField for Field<'_, T> {
+    fn validate_field(&self, _: &str, _: &mut ValidationErrors) {}
+}
+
+/// Appends `token` to the JSON pointer `path`.
+pub fn join(path: &str, token: &str) -> String {
+    format!("{}/{}", path, token.replace('~', "~0").replace('/', "~1"))
+}
+
+/// A number which `minimum`, `maximum` and `multipleOf` apply to.
+pub trait Number: Copy + fmt::Display {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! number {
+    ($($t:ty),*) => {
+        $(
+            impl Number for $t {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+number!(i8, i16, i32, i64, i128, u8, u16, u32, u64, u128, f32, f64);
+
+pub fn minimum<N: Number>(
+    value: N,
+    minimum: f64,
+    exclusive: bool,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    let number = value.to_f64();
+    if number < minimum || (exclusive && number == minimum) {
+        let bound = if exclusive {
+            "greater than"
+        } else {
+            "at least"
+        };
+        errors.push(ValidationError::new(
+            path,
+            if exclusive {
+                "exclusiveMinimum"
+            } else {
+                "minimum"
+            },
+            format!("{} is not {} {}", value, bound, minimum),
+        ));
+    }
+}
+
+pub fn maximum<N: Number>(
+    value: N,
+    maximum: f64,
+    exclusive: bool,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    let number = value.to_f64();
+    if number > maximum || (exclusive && number == maximum) {
+        let bound = if exclusive { "less than" } else { "at most" };
+        errors.push(ValidationError::new(
+            path,
+            if exclusive {
+                "exclusiveMaximum"
+            } else {
+                "maximum"
+            },
+            format!("{} is not {} {}", value, bound, maximum),
+        ));
+    }
+}
+
+pub fn multiple_of<N: Number>(
+    value: N,
+    multiple_of: f64,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    let quotient = value.to_f64() / multiple_of;
+    // Leave some room for the rounding of decimal fractions, such as `0.1`.
+    // A quotient too large for an `f64` cannot be a whole number either
+    if !quotient.is_finite() || (quotient - quotient.round()).abs() > 1e-9 * quotient.abs().max(1.0)
+    {
+        errors.push(ValidationError::new(
+            path,
+            "multipleOf",
+            format!("{} is not a multiple of {}", value, multiple_of),
+        ));
+    }
+}
+
+pub fn min_length(value: &str, min_length: usize, path: &str, errors: &mut ValidationErrors) {
+    if value.chars().count() < min_length {
+        errors.push(ValidationError::new(
+            path,
+            "minLength",
+            format!("{:?} is shorter than {} characters", value, min_length),
+        ));
+    }
+}
+
+pub fn max_length(value: &str, max_length: usize, path: &str, errors: &mut ValidationErrors) {
+    if value.chars().count() > max_length {
+        errors.push(ValidationError::new(
+            path,
+            "maxLength",
+            format!("{:?} is longer than {} characters", value, max_length),
+        ));
+    }
+}
+
+thread_local! {
+    static REGEXES: RefCell<HashMap<&'static str, Option<Regex>>> = RefCell::default();
+}
+
+/// Whether `value` matches the regular expression `pattern`, `None` if it is
+/// not supported.
+fn is_match(value: &str, pattern: &'static str) -> Option<bool> {
+    REGEXES.with(|regexes| {
+        regexes
+            .borrow_mut()
+            .entry(pattern)
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .as_ref()
+            .map(|regex| regex.is_match(value))
+    })
+}
+
+fn unsupported_pattern(pattern: &str, path: &str, keyword: &'static str) -> ValidationError {
+    ValidationError::new(
+        path,
+        keyword,
+        format!("`{}` is not a supported regular expression", pattern),
+    )
+}
+
+pub fn pattern(value: &str, pattern: &'static str, path: &str, errors: &mut ValidationErrors) {
+    match is_match(value, pattern) {
+        Some(true) => (),
+        Some(false) => errors.push(ValidationError::new(
+            path,
+            "pattern",
+            format!("{:?} does not match `{}`", value, pattern),
+        )),
+        None => errors.push(unsupported_pattern(pattern, path, "pattern")),
+    }
+}
+
+/// Checks that the `names` of the properties which an object does not declare
+/// match one of the `patterns` of its `patternProperties`, as it does not
+/// allow additional properties.
+pub fn pattern_properties<'a>(
+    names: impl IntoIterator<Item = &'a String>,
+    patterns: &[&'static str],
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    for name in names {
+        let matches = patterns
+            .iter()
+            .map(|&pattern| (pattern, is_match(name, pattern)))
+            .collect::<Vec<_>>();
+        if matches.iter().any(|&(_, matches)| matches == Some(true)) {
+            continue;
+        }
+        match matches.iter().find(|(_, matches)| matches.is_none()) {
+            Some(&(pattern, _)) => {
+                errors.push(unsupported_pattern(pattern, path, "patternProperties"))
+            }
+            None => errors.push(ValidationError::new(
+                &join(path, name),
+                "additionalProperties",
+                format!("{:?} matches none of the patterns of the properties", name),
+            )),
+        }
+    }
+}
+
+/// Deserializes the properties which an object does not declare, rejecting
+/// those which [`pattern_properties`] does not allow. The generated types
+/// deserialize their `patternProperties` with it if validating newtypes are
+/// enabled.
+pub fn deserialize_pattern_properties<'de, D, T>(
+    deserializer: D,
+    patterns: &[&'static str],
+) -> Result<BTreeMap<String, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let properties = BTreeMap::<String, T>::deserialize(deserializer)?;
+    let mut errors = ValidationErrors::new();
+    pattern_properties(properties.keys(), patterns, "", &mut errors);
+    errors
+        .into_result()
+        .map(|()| properties)
+        .map_err(de::Error::custom)
+}
+
+pub fn min_items(len: usize, min_items: usize, path: &str, errors: &mut ValidationErrors) {
+    if len < min_items {
+        errors.push(ValidationError::new(
+            path,
+            "minItems",
+            format!("{} items are fewer than {}", len, min_items),
+        ));
+    }
+}
+
+pub fn max_items(len: usize, max_items: usize, path: &str, errors: &mut ValidationErrors) {
+    if len > max_items {
+        errors.push(ValidationError::new(
+            path,
+            "maxItems",
+            format!("{} items are more than {}", len, max_items),
+        ));
+    }
+}
+
+pub fn unique_items<T: PartialEq>(items: &[T], path: &str, errors: &mut ValidationErrors) {
+    let duplicate = items
+        .iter()
+        .enumerate()
+        .any(|(i, item)| items[..i].contains(item));
+    if duplicate {
+        errors.push(ValidationError::new(
+            path,
+            "uniqueItems",
+            "the items are not unique",
+        ));
+    }
+}
+
+pub fn min_properties(
+    len: usize,
+    min_properties: usize,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    if len < min_properties {
+        errors.push(ValidationError::new(
+            path,
+            "minProperties",
+            format!("{} properties are fewer than {}", len, min_properties),
+        ));
+    }
+}
+
+pub fn max_properties(
+    len: usize,
+    max_properties: usize,
+    path: &str,
+    errors: &mut ValidationErrors,
+) {
+    if len > max_properties {
+        errors.push(ValidationError::new(
+            path,
+            "maxProperties",
+            format!("{} properties are more than {}", len, max_properties),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_paths() {
+        let values = vec![Some(String::from("a")), None];
+        let mut errors = ValidationErrors::new();
+        values.validate_into("/tags", &mut errors);
+        assert!(errors.is_empty());
+
+        max_length("abc", 2, &join("/tags", "a/b"), &mut errors);
+        minimum(1.5, 2.0, true, "/size", &mut errors);
+        let errors = errors.into_result().unwrap_err();
+        assert_eq!(
+            errors.to_string(),
+            "/tags/a~1b: \"abc\" is longer than 2 characters\n\
+             /size: 1.5 is not greater than 2"
+        );
+        assert_eq!(
+            errors.iter().map(|e| e.keyword()).collect::<Vec<_>>(),
+            ["maxLength", "exclusiveMinimum"]
+        );
+    }
+
+    #[test]
+    fn multiple_of_decimal() {
+        let mut errors = ValidationErrors::new();
+        multiple_of(0.3, 0.1, "", &mut errors);
+        multiple_of(7, 2.0, "", &mut errors);
+        assert_eq!(errors.len(), 1);
+        multiple_of(1e308, 0.123456789, "", &mut errors);
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn check_pattern() {
+        let mut errors = ValidationErrors::new();
+        pattern("abc", "^a", "", &mut errors);
+        pattern("abc", "^b", "", &mut errors);
+        assert_eq!(
+            errors.iter().next().unwrap().message(),
+            "\"abc\" does not match `^b`"
+        );
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn check_pattern_properties() {
+        let patterns = ["^x-", "^[0-9]+$"];
+        let properties: BTreeMap<String, u8> = deserialize_pattern_properties(
+            &mut serde_json::Deserializer::from_str(r#"{"x-a": 1, "12": 2}"#),
+            &patterns,
+        )
+        .unwrap();
+        assert_eq!(properties.len(), 2);
+        let error = deserialize_pattern_properties::<_, u8>(
+            &mut serde_json::Deserializer::from_str(r#"{"x-a": 1, "y": 2}"#),
+            &patterns,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "/y: \"y\" matches none of the patterns of the properties"
+        );
+
+        let mut errors = ValidationErrors::new();
+        pattern_properties(&[String::from("a")], &["("], "/map", &mut errors);
+        assert_eq!(
+            errors.iter().next().unwrap().to_string(),
+            "/map: `(` is not a supported regular expression"
+        );
+    }
+
+    #[test]
+    fn skip_fields_without_validate() {
+        struct Opaque;
+        struct Invalid;
+        impl Validate for Invalid {
+            fn validate_into(&self, path: &str, errors: &mut ValidationErrors) {
+                errors.push(ValidationError::new(path, "const", "invalid"));
+            }
+        }
+        let mut errors = ValidationErrors::new();
+        validate_field!(&Opaque, "/opaque", &mut errors);
+        validate_field!(&vec![Opaque], "/opaques", &mut errors);
+        assert!(errors.is_empty());
+        validate_field!(&vec![Invalid], "/invalid", &mut errors);
+        assert_eq!(errors.to_string(), "/invalid/0: invalid");
+    }
+}
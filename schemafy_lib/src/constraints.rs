@@ -0,0 +1,480 @@
+//! Generation of the code checking the constraints which serde does not
+//! enforce, such as `minimum` or `pattern`: the `validate` methods and the
+//! validating newtypes.
+
+use proc_macro2::TokenStream;
+
+use serde_json::Value;
+
+use crate::{
+    additional_properties, constant, discriminated_union, Error, Expander, Schema, SimpleTypes,
+};
+
+/// Whether the constraints of `schema`, such as `minimum`, apply to the type
+/// generated for it, rather than to an enum or a union.
+fn is_constrainable(schema: &Schema) -> bool {
+    constant(schema).is_none()
+        && schema.enum_.is_none()
+        && discriminated_union(schema).is_none()
+        && schema.one_of.is_none()
+        && schema.any_of.is_none()
+}
+
+/// Whether an object `schema` is generated as a map rather than a struct.
+fn is_map(schema: &Schema) -> bool {
+    schema.properties.is_empty()
+        && schema.pattern_properties.is_empty()
+        && additional_properties(schema) != Some(Err(false))
+}
+
+/// `schema` without the constraints which the type generated for it does not
+/// enforce.
+fn without_constraints(schema: &Schema) -> Schema {
+    Schema {
+        minimum: None,
+        maximum: None,
+        exclusive_minimum: false,
+        exclusive_maximum: false,
+        multiple_of: None,
+        min_length: None,
+        max_length: None,
+        pattern: None,
+        min_items: None,
+        max_items: None,
+        unique_items: false,
+        min_properties: None,
+        max_properties: None,
+        ..schema.clone()
+    }
+}
+
+// The checks below are statements checking a `value` in scope at the JSON
+// pointer `path`, adding the violated constraints to `errors`.
+
+/// The checks of `minimum`, `maximum` and `multipleOf` for a number.
+fn number_checks(core: &TokenStream, schema: &Schema) -> Vec<TokenStream> {
+    let mut checks = Vec::new();
+    if let Some(minimum) = schema.minimum {
+        let exclusive = schema.exclusive_minimum;
+        checks.push(quote! {
+            #core validate::minimum(*value, #minimum, #exclusive, &path, errors);
+        });
+    }
+    if let Some(maximum) = schema.maximum {
+        let exclusive = schema.exclusive_maximum;
+        checks.push(quote! {
+            #core validate::maximum(*value, #maximum, #exclusive, &path, errors);
+        });
+    }
+    if let Some(multiple_of) = schema.multiple_of {
+        checks.push(quote! {
+            #core validate::multiple_of(*value, #multiple_of, &path, errors);
+        });
+    }
+    checks
+}
+
+/// The checks of `minLength`, `maxLength` and `pattern` for a string.
+fn string_checks(core: &TokenStream, schema: &Schema) -> Vec<TokenStream> {
+    let mut checks = Vec::new();
+    if let Some(min_length) = schema.min_length.as_ref().and_then(count_literal) {
+        checks.push(quote! {
+            #core validate::min_length(value, #min_length, &path, errors);
+        });
+    }
+    if let Some(max_length) = schema
+        .max_length
+        .map(Value::from)
+        .as_ref()
+        .and_then(count_literal)
+    {
+        checks.push(quote! {
+            #core validate::max_length(value, #max_length, &path, errors);
+        });
+    }
+    if let Some(ref pattern) = schema.pattern {
+        checks.push(quote! {
+            #core validate::pattern(value, #pattern, &path, errors);
+        });
+    }
+    checks
+}
+
+/// The checks of `minItems`, `maxItems` and `uniqueItems` for a `Vec`.
+fn array_checks(core: &TokenStream, schema: &Schema) -> Vec<TokenStream> {
+    let mut checks = Vec::new();
+    if let Some(min_items) = schema.min_items.as_ref().and_then(count_literal) {
+        checks.push(quote! {
+            #core validate::min_items(value.len(), #min_items, &path, errors);
+        });
+    }
+    if let Some(max_items) = schema
+        .max_items
+        .map(Value::from)
+        .as_ref()
+        .and_then(count_literal)
+    {
+        checks.push(quote! {
+            #core validate::max_items(value.len(), #max_items, &path, errors);
+        });
+    }
+    if schema.unique_items {
+        checks.push(quote! {
+            #core validate::unique_items(value, &path, errors);
+        });
+    }
+    checks
+}
+
+/// The checks of `minProperties` and `maxProperties` for a map.
+fn object_checks(core: &TokenStream, schema: &Schema) -> Vec<TokenStream> {
+    let mut checks = Vec::new();
+    if let Some(min_properties) = schema.min_properties.as_ref().and_then(count_literal) {
+        checks.push(quote! {
+            #core validate::min_properties(value.len(), #min_properties, &path, errors);
+        });
+    }
+    if let Some(max_properties) = schema
+        .max_properties
+        .map(Value::from)
+        .as_ref()
+        .and_then(count_literal)
+    {
+        checks.push(quote! {
+            #core validate::max_properties(value.len(), #max_properties, &path, errors);
+        });
+    }
+    checks
+}
+
+/// The `usize` literal of a count such as `maxLength`.
+fn count_literal(count: &Value) -> Option<proc_macro2::Literal> {
+    count
+        .as_u64()
+        .map(|count| proc_macro2::Literal::usize_unsuffixed(count as usize))
+}
+
+impl<'r> Expander<'r> {
+    /// Implements `Validate` for the generated type `name`, with `checks`
+    /// adding the constraints `self` violates at `path` to `errors`. Nothing
+    /// if validation is disabled.
+    pub(crate) fn validate_impl(&self, name: &syn::Ident, checks: TokenStream) -> TokenStream {
+        if !self.validate {
+            return TokenStream::new();
+        }
+        let core = self.schemafy_path();
+        let (path, errors) = if checks.is_empty() {
+            (quote!(_), quote!(_))
+        } else {
+            (quote!(path), quote!(errors))
+        };
+        quote! {
+            impl #core validate::Validate for #name {
+                fn validate_into(&self, #path: &str, #errors: &mut #core validate::ValidationErrors) {
+                    #checks
+                }
+            }
+        }
+    }
+
+    /// Implements `Validate` and a `validate` method for the struct `name`
+    /// generated for `schema`, with the `validations` of its fields and the
+    /// expressions telling whether each field is `present`.
+    pub(crate) fn validate_struct(
+        &self,
+        name: &syn::Ident,
+        schema: &Schema,
+        validations: &[TokenStream],
+        present: &[TokenStream],
+    ) -> TokenStream {
+        if !self.validate {
+            return TokenStream::new();
+        }
+        let core = self.schemafy_path();
+        let mut checks = Vec::new();
+        if let Some(min_properties) = schema.min_properties.as_ref().and_then(count_literal) {
+            checks.push(quote! {
+                #core validate::min_properties(len, #min_properties, path, errors);
+            });
+        }
+        if let Some(max_properties) = schema
+            .max_properties
+            .map(Value::from)
+            .as_ref()
+            .and_then(count_literal)
+        {
+            checks.push(quote! {
+                #core validate::max_properties(len, #max_properties, path, errors);
+            });
+        }
+        let len = if checks.is_empty() {
+            None
+        } else {
+            Some(quote! {
+                let len: usize = [#(#present),*].iter().sum();
+            })
+        };
+        let validate = self.validate_impl(
+            name,
+            quote! {
+                #len
+                #(#checks)*
+                #(#validations)*
+            },
+        );
+        quote! {
+            #validate
+            impl #name {
+                /// Checks the constraints of the schema which serde does not
+                /// enforce, such as `minimum` or `pattern`.
+                pub fn validate(&self) -> Result<(), #core validate::ValidationErrors> {
+                    #core validate::Validate::validate(self)
+                }
+            }
+        }
+    }
+
+    /// Implements `Validate` for the generated enum `name`, by validating the
+    /// value of its `variants` which have one.
+    pub(crate) fn validate_variants(
+        &self,
+        name: &syn::Ident,
+        variants: &[(syn::Ident, bool)],
+    ) -> TokenStream {
+        let core = self.schemafy_path();
+        let values = variants
+            .iter()
+            .filter(|(_, value)| *value)
+            .map(|(variant, _)| variant)
+            .collect::<Vec<_>>();
+        let checks = if values.is_empty() {
+            TokenStream::new()
+        } else {
+            let units = if values.len() == variants.len() {
+                None
+            } else {
+                Some(quote!(_ => (),))
+            };
+            quote! {
+                match *self {
+                    #( #name::#values(ref value) => #core validate::validate_field!(value, path, errors), )*
+                    #units
+                }
+            }
+        };
+        self.validate_impl(name, checks)
+    }
+
+    /// The checks of the constraints of `schema` which the type generated for
+    /// it does not enforce, for a `value` in scope at the JSON pointer `path`.
+    /// `None` if there is nothing to check, or validation is disabled.
+    ///
+    /// Only the constraints of plain numbers, strings, arrays and maps are
+    /// checked here, generated types check their own constraints.
+    pub(crate) fn validation_checks(
+        &mut self,
+        schema: &Schema,
+    ) -> Result<Option<TokenStream>, Error> {
+        // Validating newtypes enforce the constraints themselves
+        if !self.validate || self.validating_newtypes {
+            return Ok(None);
+        }
+        // The constraints do not apply to the types which formats are mapped
+        // to, or which schemas are overridden with
+        let overridden = match schema.ref_ {
+            Some(ref ref_) => self.type_override_at(&self.ref_location(ref_)?).is_some(),
+            None => self.type_override().is_some(),
+        };
+        let schema = self.schema(schema)?;
+        if overridden || !is_constrainable(&schema) || self.format_type(&schema).is_some() {
+            return Ok(None);
+        }
+        let core = self.schemafy_path();
+        let mut checks = Vec::new();
+        match schema.type_[..] {
+            [SimpleTypes::Integer] | [SimpleTypes::Number] => {
+                checks.extend(number_checks(&core, &schema));
+            }
+            [SimpleTypes::String] => checks.extend(string_checks(&core, &schema)),
+            // `prefixItems` are generated as a tuple
+            [SimpleTypes::Array] if schema.prefix_items.is_none() => {
+                checks.extend(array_checks(&core, &schema));
+                if let Some(Ok(item)) = schema.items.as_ref().map(|items| items.as_schema()) {
+                    if let Some(item_checks) =
+                        self.descend(&["items"], |this| this.validation_checks(item))?
+                    {
+                        checks.push(quote! {
+                            for (i, value) in value.iter().enumerate() {
+                                let path = #core validate::join(&path, &i.to_string());
+                                #item_checks
+                            }
+                        });
+                    }
+                }
+            }
+            [SimpleTypes::Object] if is_map(&schema) => {
+                checks.extend(object_checks(&core, &schema));
+                let keyword = if schema.additional_properties.is_some() {
+                    "additionalProperties"
+                } else {
+                    "unevaluatedProperties"
+                };
+                if let Some(Ok(property)) = additional_properties(&schema) {
+                    if let Some(property_checks) =
+                        self.descend(&[keyword], |this| this.validation_checks(property))?
+                    {
+                        checks.push(quote! {
+                            for (key, value) in value.iter() {
+                                let path = #core validate::join(&path, key);
+                                #property_checks
+                            }
+                        });
+                    }
+                }
+            }
+            _ => (),
+        }
+        Ok(if checks.is_empty() {
+            None
+        } else {
+            Some(quote!( #(#checks)* ))
+        })
+    }
+
+    /// The checks of a validating newtype for `schema`, which is not a
+    /// reference, or `None` if the schema has no constraints to check or
+    /// validating newtypes are disabled.
+    ///
+    /// The checks of the items of arrays and the values of maps are left to
+    /// the newtypes generated for them.
+    pub(crate) fn newtype_checks(&self, schema: &Schema) -> Option<TokenStream> {
+        if !self.validating_newtypes
+            || !is_constrainable(schema)
+            || self.format_type(schema).is_some()
+            || self.type_override().is_some()
+        {
+            return None;
+        }
+        let core = self.schemafy_path();
+        let checks = match schema.type_[..] {
+            [SimpleTypes::Integer] | [SimpleTypes::Number] => number_checks(&core, schema),
+            [SimpleTypes::String] => string_checks(&core, schema),
+            [SimpleTypes::Array] if schema.prefix_items.is_none() => array_checks(&core, schema),
+            [SimpleTypes::Object] if is_map(schema) => object_checks(&core, schema),
+            // The constraints only apply to values of the matching type
+            [] => {
+                let number_checks = number_checks(&core, schema);
+                let string_checks = string_checks(&core, schema);
+                let array_checks = array_checks(&core, schema);
+                let object_checks = object_checks(&core, schema);
+                let mut arms = Vec::new();
+                if !number_checks.is_empty() {
+                    arms.push(quote! {
+                        serde_json::Value::Number(ref number) => {
+                            if let Some(ref value) = number.as_f64() {
+                                #(#number_checks)*
+                            }
+                        }
+                    });
+                }
+                if !string_checks.is_empty() {
+                    arms.push(quote! {
+                        serde_json::Value::String(ref value) => { #(#string_checks)* }
+                    });
+                }
+                if !array_checks.is_empty() {
+                    arms.push(quote! {
+                        serde_json::Value::Array(ref value) => { #(#array_checks)* }
+                    });
+                }
+                if !object_checks.is_empty() {
+                    arms.push(quote! {
+                        serde_json::Value::Object(ref value) => { #(#object_checks)* }
+                    });
+                }
+                if arms.is_empty() {
+                    return None;
+                }
+                vec![quote! {
+                    match *value {
+                        #(#arms)*
+                        _ => (),
+                    }
+                }]
+            }
+            _ => Vec::new(),
+        };
+        if checks.is_empty() {
+            None
+        } else {
+            Some(quote!( #(#checks)* ))
+        }
+    }
+
+    /// Generates a newtype named `name` around the type of `schema`, which
+    /// can only be constructed, and deserialized, from values passing the
+    /// `checks` of its constraints.
+    pub(crate) fn expand_newtype(
+        &mut self,
+        name: &syn::Ident,
+        schema: &Schema,
+        checks: TokenStream,
+    ) -> Result<TokenStream, Error> {
+        // Types defined inline are named after the newtype: `{name}Item` for
+        // the items of arrays and `{name}Value` for the values of maps
+        let saved_type = std::mem::replace(&mut self.current_type, name.to_string());
+        let saved_field = std::mem::replace(
+            &mut self.current_field,
+            if schema.type_ == [SimpleTypes::Object] {
+                "Value".into()
+            } else {
+                String::new()
+            },
+        );
+        // The bounds of integers still choose the width of the inner type
+        let inner = if schema.type_ == [SimpleTypes::Integer] {
+            Ok(self.integer_type(schema).into())
+        } else {
+            self.expand_type_(&without_constraints(schema))
+        };
+        self.current_type = saved_type;
+        self.current_field = saved_field;
+        let inner = inner?.typ.parse::<TokenStream>().unwrap();
+        let inner_name = inner.to_string();
+        let core = self.schemafy_path();
+        let validate = self.validate_impl(
+            name,
+            quote!(#core validate::validate_field!(&self.0, path, errors);),
+        );
+        Ok(quote! {
+            #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+            #[serde(try_from = #inner_name)]
+            pub struct #name(#inner);
+            impl ::std::convert::TryFrom<#inner> for #name {
+                type Error = #core validate::ValidationErrors;
+                fn try_from(value: #inner) -> Result<Self, Self::Error> {
+                    let mut errors = #core validate::ValidationErrors::new();
+                    {
+                        let value = &value;
+                        let path = String::new();
+                        let errors = &mut errors;
+                        #checks
+                    }
+                    errors.into_result().map(|()| #name(value))
+                }
+            }
+            impl ::std::ops::Deref for #name {
+                type Target = #inner;
+                fn deref(&self) -> &#inner {
+                    &self.0
+                }
+            }
+            impl From<#name> for #inner {
+                fn from(value: #name) -> Self {
+                    value.0
+                }
+            }
+            #validate
+        })
+    }
+}
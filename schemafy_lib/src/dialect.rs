@@ -0,0 +1,328 @@
+//! Detection of the JSON Schema draft a schema is written against.
+//!
+//! [`Schema`](crate::Schema) models draft 4 together with the
+//! keywords that later drafts added. Schemas written against a later draft
+//! are rewritten into that model with [`Dialect::normalize`] before they are
+//! deserialized, for instance boolean schemas become `{}` and `{"not": {}}`.
+
+use serde_json::{json, Map, Value};
+
+/// A JSON Schema draft.
+///
+/// Schemas without a (known) `$schema` are treated as draft 4.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dialect {
+    #[default]
+    Draft4,
+    Draft6,
+    Draft7,
+    Draft201909,
+    Draft202012,
+}
+
+/// Keywords whose value is a single subschema.
+///
+/// `contains`, `propertyNames` and `if` are only normalized, the
+/// [`Expander`](crate::Expander) does not generate any code for them.
+const SCHEMA_KEYWORDS: &[&str] = &["contains", "else", "if", "not", "propertyNames", "then"];
+
+/// Keywords whose value is a subschema which, like `additionalProperties`, is
+/// modelled as either a boolean or a schema, so `true` and `false` are kept as is.
+const BOOL_OR_SCHEMA_KEYWORDS: &[&str] = &[
+    "additionalItems",
+    "additionalProperties",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// Keywords whose value is an array of subschemas.
+const SCHEMA_ARRAY_KEYWORDS: &[&str] = &["allOf", "anyOf", "oneOf", "prefixItems"];
+
+/// Keywords whose value is an object of subschemas.
+const SCHEMA_MAP_KEYWORDS: &[&str] = &[
+    "$defs",
+    "definitions",
+    "dependentSchemas",
+    "patternProperties",
+    "properties",
+];
+
+impl Dialect {
+    /// Returns the dialect identified by a `$schema` URI.
+    pub fn from_uri(uri: &str) -> Option<Dialect> {
+        let uri = uri.trim_end_matches('#');
+        let uri = uri.split_once("://").map_or(uri, |(_, rest)| rest);
+        match uri {
+            "json-schema.org/draft-04/schema" => Some(Dialect::Draft4),
+            "json-schema.org/draft-06/schema" => Some(Dialect::Draft6),
+            "json-schema.org/draft-07/schema" => Some(Dialect::Draft7),
+            "json-schema.org/draft/2019-09/schema" => Some(Dialect::Draft201909),
+            "json-schema.org/draft/2020-12/schema" | "json-schema.org/schema" => {
+                Some(Dialect::Draft202012)
+            }
+            _ => None,
+        }
+    }
+
+    /// Detects the dialect of a schema document from its `$schema` keyword.
+    pub fn detect(schema: &Value) -> Dialect {
+        schema
+            .get("$schema")
+            .and_then(Value::as_str)
+            .and_then(Dialect::from_uri)
+            .unwrap_or_default()
+    }
+
+    /// Rewrites `schema` (and all of its subschemas) into the form expected by
+    /// [`Schema`](crate::Schema).
+    pub fn normalize(self, schema: &mut Value) {
+        match schema {
+            Value::Bool(true) => *schema = Value::Object(Map::new()),
+            Value::Bool(false) => *schema = json!({ "not": {} }),
+            Value::Object(map) => self.normalize_object(map),
+            _ => (),
+        }
+    }
+
+    fn normalize_object(self, map: &mut Map<String, Value>) {
+        // An embedded `$schema` switches the dialect for that subschema
+        let dialect = map
+            .get("$schema")
+            .and_then(Value::as_str)
+            .and_then(Dialect::from_uri)
+            .unwrap_or(self);
+
+        for &keyword in SCHEMA_KEYWORDS {
+            if let Some(schema) = map.get_mut(keyword) {
+                dialect.normalize(schema);
+            }
+        }
+        for &keyword in BOOL_OR_SCHEMA_KEYWORDS {
+            if let Some(schema @ Value::Object(_)) = map.get_mut(keyword) {
+                dialect.normalize(schema);
+            }
+        }
+        for &keyword in SCHEMA_ARRAY_KEYWORDS {
+            if let Some(Value::Array(schemas)) = map.get_mut(keyword) {
+                schemas
+                    .iter_mut()
+                    .for_each(|schema| dialect.normalize(schema));
+            }
+        }
+        for &keyword in SCHEMA_MAP_KEYWORDS {
+            if let Some(Value::Object(schemas)) = map.get_mut(keyword) {
+                schemas
+                    .values_mut()
+                    .for_each(|schema| dialect.normalize(schema));
+            }
+        }
+        match map.get_mut("items") {
+            Some(Value::Array(schemas)) => schemas
+                .iter_mut()
+                .for_each(|schema| dialect.normalize(schema)),
+            Some(schema) => dialect.normalize(schema),
+            None => (),
+        }
+        // `dependencies` mixes subschemas with arrays of property names
+        if let Some(Value::Object(dependencies)) = map.get_mut("dependencies") {
+            dependencies
+                .values_mut()
+                .filter(|dependency| !dependency.is_array())
+                .for_each(|schema| dialect.normalize(schema));
+        }
+        // `dependentRequired` took over the property lists of `dependencies`
+        if dialect >= Dialect::Draft201909 {
+            if let Some(Value::Object(required)) = map.remove("dependentRequired") {
+                let dependencies = map
+                    .entry("dependencies")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(dependencies) = dependencies {
+                    for (property, names) in required {
+                        dependencies.entry(property).or_insert(names);
+                    }
+                }
+            }
+        }
+
+        normalize_exclusive_bound(map, "minimum", "exclusiveMinimum", |bound, exclusive| {
+            exclusive >= bound
+        });
+        normalize_exclusive_bound(map, "maximum", "exclusiveMaximum", |bound, exclusive| {
+            exclusive <= bound
+        });
+
+        // `Schema` cannot tell `"const": null` from a missing `const`, but a
+        // single-element `enum` is the same constant
+        if map.get("const") == Some(&Value::Null) {
+            map.remove("const");
+            map.insert("enum".into(), json!([null]));
+            map.entry("type").or_insert_with(|| "null".into());
+        }
+        // `const` implies the type of the instance
+        if !map.contains_key("type") {
+            let type_ = match map.get("const") {
+                Some(Value::String(_)) => Some("string"),
+                Some(Value::Bool(_)) => Some("boolean"),
+                Some(Value::Number(n)) if n.is_f64() => Some("number"),
+                Some(Value::Number(_)) => Some("integer"),
+                _ => None,
+            };
+            if let Some(type_) = type_ {
+                map.insert("type".into(), type_.into());
+            }
+        }
+
+        // Recursive and dynamic references are resolved statically
+        let dynamic_ref = match dialect {
+            Dialect::Draft4 | Dialect::Draft6 | Dialect::Draft7 => None,
+            Dialect::Draft201909 => map.remove("$recursiveRef"),
+            Dialect::Draft202012 => map.remove("$dynamicRef"),
+        };
+        if let Some(ref_) = dynamic_ref {
+            map.entry("$ref").or_insert(ref_);
+        }
+        if dialect == Dialect::Draft202012 {
+            if let Some(anchor) = map.remove("$dynamicAnchor") {
+                map.entry("$anchor").or_insert(anchor);
+            }
+        }
+    }
+}
+
+/// Since draft 6 `exclusiveMinimum` and `exclusiveMaximum` are bounds of their
+/// own instead of flags modifying `minimum` and `maximum`. Folds them back into
+/// the draft 4 form, keeping whichever bound is the stricter one.
+fn normalize_exclusive_bound(
+    map: &mut Map<String, Value>,
+    bound: &str,
+    exclusive: &str,
+    is_stricter: impl Fn(f64, f64) -> bool,
+) {
+    let exclusive_bound = match map.get(exclusive) {
+        Some(Value::Number(n)) => n.clone(),
+        _ => return,
+    };
+    let stricter = match map.get(bound).and_then(Value::as_f64) {
+        Some(b) => is_stricter(b, exclusive_bound.as_f64().unwrap_or(b)),
+        None => true,
+    };
+    if stricter {
+        map.insert(bound.into(), Value::Number(exclusive_bound));
+    }
+    map.insert(exclusive.into(), Value::Bool(stricter));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect() {
+        assert_eq!(
+            Dialect::detect(&json!({ "$schema": "http://json-schema.org/draft-04/schema#" })),
+            Dialect::Draft4
+        );
+        assert_eq!(
+            Dialect::detect(&json!({ "$schema": "http://json-schema.org/draft-06/schema#" })),
+            Dialect::Draft6
+        );
+        assert_eq!(
+            Dialect::detect(&json!({ "$schema": "http://json-schema.org/draft-07/schema#" })),
+            Dialect::Draft7
+        );
+        assert_eq!(
+            Dialect::detect(&json!({ "$schema": "https://json-schema.org/draft/2019-09/schema" })),
+            Dialect::Draft201909
+        );
+        assert_eq!(
+            Dialect::detect(&json!({ "$schema": "https://json-schema.org/draft/2020-12/schema" })),
+            Dialect::Draft202012
+        );
+        assert_eq!(Dialect::detect(&json!({})), Dialect::Draft4);
+    }
+
+    #[test]
+    fn normalize_boolean_schemas() {
+        let mut schema = json!({
+            "properties": { "a": true, "b": false },
+            "items": [true],
+            "additionalProperties": false,
+            "if": { "propertyNames": false },
+            "then": true,
+        });
+        Dialect::Draft7.normalize(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "properties": { "a": {}, "b": { "not": {} } },
+                "items": [{}],
+                "additionalProperties": false,
+                "if": { "propertyNames": { "not": {} } },
+                "then": {},
+            })
+        );
+    }
+
+    #[test]
+    fn normalize_exclusive_bounds() {
+        let mut schema = json!({ "minimum": 1, "exclusiveMinimum": 3, "exclusiveMaximum": 10 });
+        Dialect::Draft202012.normalize(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "minimum": 3,
+                "exclusiveMinimum": true,
+                "maximum": 10,
+                "exclusiveMaximum": true,
+            })
+        );
+
+        let mut schema = json!({ "minimum": 5, "exclusiveMinimum": 3 });
+        Dialect::Draft202012.normalize(&mut schema);
+        assert_eq!(schema, json!({ "minimum": 5, "exclusiveMinimum": false }));
+    }
+
+    #[test]
+    fn normalize_dependent_required() {
+        let mut schema = json!({
+            "$schema": "https://json-schema.org/draft/2019-09/schema",
+            "dependentRequired": { "card": ["billing"] },
+            "dependentSchemas": { "name": { "required": ["id"] } },
+        });
+        Dialect::detect(&schema).normalize(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2019-09/schema",
+                "dependencies": { "card": ["billing"] },
+                "dependentSchemas": { "name": { "required": ["id"] } },
+            })
+        );
+    }
+
+    #[test]
+    fn normalize_const_and_dynamic_ref() {
+        let mut schema = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$dynamicAnchor": "node",
+            "properties": {
+                "kind": { "const": "leaf" },
+                "parent": { "const": null },
+                "children": { "items": { "$dynamicRef": "#node" } },
+            },
+        });
+        Dialect::detect(&schema).normalize(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "$anchor": "node",
+                "properties": {
+                    "kind": { "const": "leaf", "type": "string" },
+                    "parent": { "enum": [null], "type": "null" },
+                    "children": { "items": { "$ref": "#node" } },
+                },
+            })
+        );
+    }
+}
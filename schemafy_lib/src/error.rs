@@ -0,0 +1,120 @@
+//! The error type of the [`Expander`](crate::Expander) and the
+//! [`Generator`](crate::Generator).
+
+use std::fmt;
+
+use crate::{resolver, Uri};
+
+/// An error in a schema, such as a reference which cannot be resolved or an
+/// `enum` which cannot be turned into a Rust type.
+///
+/// Records the document and the JSON pointer of the schema the error was found
+/// in, as far as they are known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    message: String,
+    document: Option<Box<Uri>>,
+    pointer: String,
+    position: Option<(usize, usize)>,
+}
+
+impl Error {
+    pub(crate) fn new(message: impl Into<String>) -> Error {
+        Error {
+            message: message.into(),
+            document: None,
+            pointer: String::new(),
+            position: None,
+        }
+    }
+
+    /// Records where the error was found, unless that is already known.
+    pub(crate) fn at(mut self, document: &Uri, pointer: &str) -> Error {
+        if self.document.is_none() {
+            self.document = Some(Box::new(document.clone()));
+            self.pointer = pointer.to_owned();
+        }
+        self
+    }
+
+    pub(crate) fn with_position(mut self, line: usize, column: usize) -> Error {
+        self.position = Some((line, column));
+        self
+    }
+
+    /// The description of the error, without its location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The URI of the schema document the error was found in.
+    pub fn document(&self) -> Option<&Uri> {
+        self.document.as_deref()
+    }
+
+    /// The JSON pointer to the schema the error was found in, relative to
+    /// [`document`](Error::document). Empty for the document itself.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// The line and column (both starting at 1) within the document, known
+    /// if the document is not valid JSON, or for the schemas of files read by
+    /// the [`Generator`](crate::Generator).
+    pub fn position(&self) -> Option<(usize, usize)> {
+        self.position
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let document = match self.document {
+            Some(ref document) => resolver::display_uri(document),
+            None => return f.write_str(&self.message),
+        };
+        match self.position {
+            Some((line, column)) if self.pointer.is_empty() => {
+                write!(f, "{}:{}:{}: {}", document, line, column, self.message)
+            }
+            Some((line, column)) => write!(
+                f,
+                "{}:{}:{} (#{}): {}",
+                document, line, column, self.pointer, self.message
+            ),
+            None => write!(f, "{}#{}: {}", document, self.pointer, self.message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn display_location() {
+        let uri = resolver::file_uri(Path::new("/schemas/root.json"));
+        let error = Error::new("Unresolvable reference: `#/definitions/Missing`")
+            .at(&uri, "/properties/tags");
+        assert_eq!(
+            error.to_string(),
+            "/schemas/root.json#/properties/tags: Unresolvable reference: `#/definitions/Missing`"
+        );
+
+        let error = Error::new("expected value")
+            .at(&uri, "")
+            .with_position(3, 7);
+        assert_eq!(error.to_string(), "/schemas/root.json:3:7: expected value");
+
+        let error = Error::new("Unresolvable reference: `#/definitions/Missing`")
+            .at(&uri, "/properties/tags")
+            .with_position(4, 13);
+        assert_eq!(
+            error.to_string(),
+            "/schemas/root.json:4:13 (#/properties/tags): Unresolvable reference: `#/definitions/Missing`"
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! The Rust types of numbers and of strings with a `format`.
+
+use std::collections::BTreeMap;
+
+use crate::{Expander, Schema, SimpleTypes};
+
+/// The Rust types of the string formats: numbers encoded as strings, and the
+/// formats enabled through the features of this crate.
+pub(crate) fn default_formats(schemafy_path: &str) -> BTreeMap<String, String> {
+    let mut formats = BTreeMap::new();
+    let mut add = |format: &str, typ: String| {
+        formats.insert(format.to_owned(), typ);
+    };
+    let string_encoded = |typ: &str| format!("{}number::StringEncoded<{}>", schemafy_path, typ);
+    for (format, typ) in &INTEGER_FORMATS {
+        add(format, string_encoded(typ));
+    }
+    add("float", string_encoded("f32"));
+    add("double", string_encoded("f64"));
+    add(
+        "decimal",
+        string_encoded(&format!("{}number::Decimal", schemafy_path)),
+    );
+    if cfg!(feature = "chrono") {
+        add(
+            "date-time",
+            format!("{0}chrono::DateTime<{0}chrono::FixedOffset>", schemafy_path),
+        );
+        add("date", format!("{}chrono::NaiveDate", schemafy_path));
+        add("time", format!("{}chrono::NaiveTime", schemafy_path));
+    }
+    if cfg!(feature = "uuid") {
+        add("uuid", format!("{}uuid::Uuid", schemafy_path));
+    }
+    if cfg!(feature = "url") {
+        add("uri", format!("{}url::Url", schemafy_path));
+    }
+    if cfg!(feature = "formats") {
+        add("ipv4", "::std::net::Ipv4Addr".into());
+        add("ipv6", "::std::net::Ipv6Addr".into());
+        add("email", format!("{}format::Email", schemafy_path));
+        add("hostname", format!("{}format::Hostname", schemafy_path));
+        add("byte", format!("{}format::Base64", schemafy_path));
+        add("binary", format!("{}format::Base64", schemafy_path));
+    }
+    formats
+}
+
+/// The integer types, by their OpenAPI `format`.
+const INTEGER_FORMATS: [(&str, &str); 8] = [
+    ("int8", "i8"),
+    ("int16", "i16"),
+    ("int32", "i32"),
+    ("int64", "i64"),
+    ("uint8", "u8"),
+    ("uint16", "u16"),
+    ("uint32", "u32"),
+    ("uint64", "u64"),
+];
+
+pub(crate) const INTEGER_TYPES: [&str; 8] = ["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64"];
+
+/// The smallest integer type holding every value from `minimum` to
+/// `maximum`.
+fn bounded_integer_type(minimum: f64, maximum: f64) -> Option<&'static str> {
+    let types = if minimum >= 0.0 {
+        &INTEGER_TYPES[..4]
+    } else {
+        &INTEGER_TYPES[4..]
+    };
+    types.iter().zip(&[8, 16, 32, 64]).find_map(|(typ, bits)| {
+        let (min, max) = if minimum >= 0.0 {
+            (0.0, 2f64.powi(*bits) - 1.0)
+        } else {
+            (-(2f64.powi(bits - 1)), 2f64.powi(bits - 1) - 1.0)
+        };
+        Some(*typ).filter(|_| min <= minimum && maximum <= max)
+    })
+}
+
+/// Whether the integer type `typ` holds `n`.
+pub(crate) fn integer_fits(typ: &str, n: &serde_json::Number) -> bool {
+    let (min, max) = match typ {
+        "u8" => (0, u8::MAX.into()),
+        "u16" => (0, u16::MAX.into()),
+        "u32" => (0, u32::MAX.into()),
+        "u64" => (0, u64::MAX.into()),
+        "i8" => (i8::MIN.into(), i8::MAX.into()),
+        "i16" => (i16::MIN.into(), i16::MAX.into()),
+        "i32" => (i32::MIN.into(), i32::MAX.into()),
+        _ => (i64::MIN.into(), i64::MAX.into()),
+    };
+    n.as_i64()
+        .map(i128::from)
+        .or_else(|| n.as_u64().map(i128::from))
+        .map_or(false, |n| min <= n && n <= max)
+}
+
+impl<'r> Expander<'r> {
+    /// The Rust type of an integer schema: the one its `format` names, such as
+    /// `int32`, or else the smallest one holding every value within its
+    /// bounds, or else `i64` (`u64` for non-negative integers if unsigned
+    /// integers are enabled).
+    pub(crate) fn integer_type(&self, schema: &Schema) -> &'static str {
+        if let Some((_, typ)) = INTEGER_FORMATS
+            .iter()
+            .find(|(format, _)| schema.format.as_deref() == Some(format))
+        {
+            return typ;
+        }
+        let minimum = schema.minimum.map(|minimum| {
+            if schema.exclusive_minimum {
+                minimum.floor() + 1.0
+            } else {
+                minimum.ceil()
+            }
+        });
+        let maximum = schema.maximum.map(|maximum| {
+            if schema.exclusive_maximum {
+                maximum.ceil() - 1.0
+            } else {
+                maximum.floor()
+            }
+        });
+        match (minimum, maximum) {
+            (Some(minimum), Some(maximum)) => {
+                bounded_integer_type(minimum, maximum).unwrap_or("i64")
+            }
+            (Some(minimum), None) if minimum >= 0.0 && self.unsigned_integers => "u64",
+            _ => "i64",
+        }
+    }
+
+    /// The Rust type which the `format` of `schema` is mapped to, if any: the
+    /// type of strings of that format, or `Decimal` for `decimal` numbers.
+    pub(crate) fn format_type(&self, schema: &Schema) -> Option<String> {
+        let format = schema.format.as_ref()?;
+        if schema.enum_.is_some() {
+            return None;
+        }
+        match schema.type_[..] {
+            [SimpleTypes::String] => {
+                let typ = self.formats.get(format)?;
+                Some(typ.clone()).filter(|typ| typ != "String")
+            }
+            [SimpleTypes::Integer] | [SimpleTypes::Number] if format == "decimal" => {
+                Some(format!("{}number::Decimal", self.schemafy_path))
+            }
+            _ => None,
+        }
+    }
+}
@@ -1,5 +1,6 @@
-use crate::Expander;
+use crate::{pointer, resolver, Error, Expander, FileResolver, Resolver};
 use std::{
+    collections::BTreeMap,
     io,
     path::{Path, PathBuf},
 };
@@ -9,7 +10,7 @@ use std::{
 ///
 /// The default options are usually fine. In that case, you can use
 /// the [`generate()`](fn.generate.html) convenience method instead.
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 #[must_use]
 pub struct Generator<'a, 'b> {
     /// The name of the root type defined by the schema. If the schema
@@ -23,6 +24,53 @@ pub struct Generator<'a, 'b> {
     pub schemafy_path: &'a str,
     /// The JSON schema file to read
     pub input_file: &'b Path,
+    /// Provides the input file and the schema documents referenced from it by
+    /// their URIs, see [`file_uri`](crate::file_uri). Documents which it does
+    /// not know are read from the file system.
+    pub resolver: &'b dyn Resolver,
+    /// Whether to `include_bytes!` every file the schema was read from, so
+    /// that the generated code is rebuilt when they change. Only useful when
+    /// the code is generated by a procedural macro.
+    pub track_files: bool,
+    /// Whether the generated types get a `validate` method checking the
+    /// constraints which serde does not enforce, such as `minimum` or
+    /// `pattern`. The generated code then needs the `validate` feature of
+    /// `schemafy_core`.
+    pub validate: bool,
+    /// Whether numbers, strings, arrays and maps with constraints become
+    /// newtypes which reject values violating them while deserializing. The
+    /// generated code then needs the `validate` feature of `schemafy_core`.
+    pub validating_newtypes: bool,
+    /// Whether integers which cannot be negative but have no upper bound are
+    /// generated as `u64` rather than `i64`.
+    pub unsigned_integers: bool,
+    /// Whether structs keep the properties which their schema does not
+    /// declare, in an `extra` map, even if `additionalProperties` does not
+    /// give them a type.
+    pub extra_properties: bool,
+    /// The Rust types of strings by their `format`, overriding the types
+    /// enabled through the features of this crate
+    pub formats: BTreeMap<String, String>,
+    /// The Rust types of schemas by their JSON pointer within the root schema,
+    /// overriding the types they would get
+    pub types: BTreeMap<String, String>,
+}
+
+/// Generators are compared by their options, the resolver aside, as resolvers
+/// cannot be compared.
+impl PartialEq for Generator<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.root_name == other.root_name
+            && self.schemafy_path == other.schemafy_path
+            && self.input_file == other.input_file
+            && self.track_files == other.track_files
+            && self.validate == other.validate
+            && self.validating_newtypes == other.validating_newtypes
+            && self.unsigned_integers == other.unsigned_integers
+            && self.extra_properties == other.extra_properties
+            && self.formats == other.formats
+            && self.types == other.types
+    }
 }
 
 impl<'a, 'b> Generator<'a, 'b> {
@@ -31,32 +79,62 @@ impl<'a, 'b> Generator<'a, 'b> {
         GeneratorBuilder::default()
     }
 
+    /// Generates the types of the schema, panicking if that fails.
+    ///
+    /// See [`try_generate`](Generator::try_generate).
     pub fn generate(&self) -> proc_macro2::TokenStream {
+        self.try_generate().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Generates the types of the schema, or returns the first error found
+    /// while reading or expanding it.
+    pub fn try_generate(&self) -> Result<proc_macro2::TokenStream, Error> {
         let input_file = if self.input_file.is_relative() {
-            let crate_root = get_crate_root().unwrap();
+            let crate_root = get_crate_root()
+                .map_err(|err| Error::new(format!("Unable to find the crate root: {}", err)))?;
             crate_root.join(self.input_file)
         } else {
             PathBuf::from(self.input_file)
         };
 
-        let json = std::fs::read_to_string(&input_file).unwrap_or_else(|err| {
-            panic!("Unable to read `{}`: {}", input_file.to_string_lossy(), err)
-        });
-
-        let schema = serde_json::from_str(&json).unwrap_or_else(|err| {
-            panic!(
-                "Cannot parse `{}` as JSON: {}",
-                input_file.to_string_lossy(),
-                err
-            )
-        });
-        let mut expander = Expander::new(self.root_name.as_deref(), self.schemafy_path, &schema);
-        expander.expand(&schema)
+        // The input file and every document it references are looked up in the
+        // same sources, so relative references resolve wherever it came from
+        let resolver = (self.resolver, FileResolver);
+        let base_uri = resolver::file_uri(&input_file);
+        let schema =
+            resolver::load_document(&resolver, &base_uri, &self.input_file.display().to_string())?;
+        let mut expander = Expander::new(self.root_name.as_deref(), self.schemafy_path, &schema)
+            .with_base_uri(base_uri)
+            .with_resolver(&resolver)
+            .with_validate(self.validate)
+            .with_validating_newtypes(self.validating_newtypes)
+            .with_unsigned_integers(self.unsigned_integers)
+            .with_extra_properties(self.extra_properties);
+        for (format, typ) in &self.formats {
+            expander = expander.with_format(format, typ);
+        }
+        for (pointer, typ) in &self.types {
+            expander = expander.with_type(pointer, typ);
+        }
+        let tokens = expander.try_expand(&schema).map_err(locate)?;
+        if !self.track_files {
+            return Ok(tokens);
+        }
+        let files = expander
+            .files()
+            .into_iter()
+            .map(|file| file.to_string_lossy().into_owned());
+        Ok(quote! {
+            #tokens
+            #( const _: &[u8] = include_bytes!(#files); )*
+        })
     }
 
     pub fn generate_to_file<P: ?Sized + AsRef<Path>>(&self, output_file: &'b P) -> io::Result<()> {
         use std::process::Command;
-        let tokens = self.generate();
+        let tokens = self
+            .try_generate()
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
         let out = tokens.to_string();
         std::fs::write(output_file, &out)?;
         Command::new("rustfmt")
@@ -66,6 +144,23 @@ impl<'a, 'b> Generator<'a, 'b> {
     }
 }
 
+/// Adds the line and column to an error in a schema file, which the
+/// [`Expander`] only knows the JSON pointer of.
+fn locate(err: Error) -> Error {
+    if err.position().is_some() || err.pointer().is_empty() {
+        return err;
+    }
+    let position = err
+        .document()
+        .and_then(resolver::uri_path)
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|json| pointer::text_position(&json, err.pointer()));
+    match position {
+        Some((line, column)) => err.with_position(line, column),
+        None => err,
+    }
+}
+
 #[derive(Debug, PartialEq)]
 #[must_use]
 pub struct GeneratorBuilder<'a, 'b> {
@@ -79,6 +174,14 @@ impl<'a, 'b> Default for GeneratorBuilder<'a, 'b> {
                 root_name: None,
                 schemafy_path: "::schemafy_core::",
                 input_file: Path::new("schema.json"),
+                resolver: &FileResolver,
+                track_files: false,
+                validate: false,
+                validating_newtypes: false,
+                unsigned_integers: false,
+                extra_properties: false,
+                formats: BTreeMap::new(),
+                types: BTreeMap::new(),
             },
         }
     }
@@ -97,6 +200,38 @@ impl<'a, 'b> GeneratorBuilder<'a, 'b> {
         self.inner.input_file = input_file.as_ref();
         self
     }
+    pub fn with_resolver(mut self, resolver: &'b dyn Resolver) -> Self {
+        self.inner.resolver = resolver;
+        self
+    }
+    pub fn with_track_files(mut self, track_files: bool) -> Self {
+        self.inner.track_files = track_files;
+        self
+    }
+    pub fn with_validate(mut self, validate: bool) -> Self {
+        self.inner.validate = validate;
+        self
+    }
+    pub fn with_validating_newtypes(mut self, validating_newtypes: bool) -> Self {
+        self.inner.validating_newtypes = validating_newtypes;
+        self
+    }
+    pub fn with_unsigned_integers(mut self, unsigned_integers: bool) -> Self {
+        self.inner.unsigned_integers = unsigned_integers;
+        self
+    }
+    pub fn with_extra_properties(mut self, extra_properties: bool) -> Self {
+        self.inner.extra_properties = extra_properties;
+        self
+    }
+    pub fn with_format(mut self, format: &str, typ: &str) -> Self {
+        self.inner.formats.insert(format.to_owned(), typ.to_owned());
+        self
+    }
+    pub fn with_type(mut self, pointer: &str, typ: &str) -> Self {
+        self.inner.types.insert(pointer.to_owned(), typ.to_owned());
+        self
+    }
     pub fn with_schemafy_path(mut self, schemafy_path: &'a str) -> Self {
         self.inner.schemafy_path = schemafy_path;
         self
@@ -115,7 +250,6 @@ fn get_crate_root() -> std::io::Result<PathBuf> {
 
     for p in current_dir.ancestors() {
         if std::fs::read_dir(p)?
-            .into_iter()
             .filter_map(Result::ok)
             .any(|p| p.file_name().eq("Cargo.toml"))
         {
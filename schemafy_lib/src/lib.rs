@@ -3,17 +3,23 @@
 // #![feature(external_doc)]
 // #![doc(include = "../README.md")]
 
-//! This is a Rust crate which can take a [json schema (draft
-//! 4)](http://json-schema.org/) and generate Rust types which are
+//! This is a Rust crate which can take a [json schema (draft 4 up
+//! to 2020-12)](http://json-schema.org/) and generate Rust types which are
 //! serializable with [serde](https://serde.rs/). No checking such as
-//! `min_value` are done but instead only the structure of the schema
-//! is followed as closely as possible.
+//! `min_value` are done by default but instead only the structure of
+//! the schema is followed as closely as possible. Such constraints can
+//! be checked by opting into generated `validate` methods, see
+//! [`Expander::with_validate`].
 //!
 //! As a schema could be arbitrarily complex this crate makes no
 //! guarantee that it can generate good types or even any types at all
 //! for a given schema but the crate does manage to bootstrap itself
 //! which is kind of cool.
 //!
+//! Keywords which restrict the values of a type without changing its shape,
+//! namely `contains`, `propertyNames` and `if`, are ignored. The properties
+//! of `then` and `else` become optional fields, as either of them may apply.
+//!
 //! ## Example
 //!
 //! Generated types for VS Codes [debug server protocol][]: <https://docs.rs/debugserver-types>
@@ -52,25 +58,51 @@ extern crate serde_derive;
 #[macro_use]
 extern crate quote;
 
+mod constraints;
+mod dialect;
+mod error;
+mod formats;
 pub mod generator;
+mod pointer;
+mod resolver;
+#[cfg(feature = "validator")]
+mod validator;
 
-/// Types from the JSON Schema meta-schema (draft 4).
+/// Types from the JSON Schema meta-schema (draft 4, extended with the
+/// keywords of later drafts).
 ///
 /// This module is itself generated from a JSON schema.
 mod schema;
 
-use std::{borrow::Cow, convert::TryFrom};
+use std::{
+    borrow::Cow,
+    cell::{Ref, RefCell, RefMut},
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+};
 
 use inflector::Inflector;
 
 use serde_json::Value;
 
-use uriparse::{Fragment, URI};
+use formats::{default_formats, integer_fits, INTEGER_TYPES};
+
+pub use schema::{
+    Discriminator, Schema, SchemaAdditionalItems, SchemaAdditionalProperties, SchemaDependencies,
+    SchemaItems, SchemaUnevaluatedItems, SchemaUnevaluatedProperties, SimpleTypes,
+};
+
+pub use dialect::Dialect;
+
+pub use error::Error;
 
-pub use schema::{Schema, SimpleTypes};
+pub use resolver::{file_uri, FileResolver, Resolver, Uri};
 
 pub use generator::{Generator, GeneratorBuilder};
 
+#[cfg(feature = "validator")]
+pub use validator::{ValidationError, Validator};
+
 use proc_macro2::{Span, TokenStream};
 
 fn replace_invalid_identifier_chars(s: &str) -> String {
@@ -153,25 +185,56 @@ fn rename_keyword(prefix: &str, s: &str) -> Option<TokenStream> {
     }
 }
 
-fn field(s: &str) -> TokenStream {
-    if let Some(t) = rename_keyword("pub", s) {
-        return t;
+fn field_ident(s: &str) -> syn::Ident {
+    let ident = str_to_ident(s);
+    if ident != s {
+        return ident;
     }
     let snake = s.to_snake_case();
-    if snake == s && !snake.contains(|c: char| c == '$' || c == '#') {
-        let field = syn::Ident::new(s, Span::call_site());
-        return quote!( pub #field );
+    if snake == s && !snake.contains(['$', '#']) {
+        return ident;
     }
 
-    let field = if snake.is_empty() {
+    if snake.is_empty() {
         syn::Ident::new("underscore", Span::call_site())
     } else {
         str_to_ident(&snake)
-    };
+    }
+}
+
+/// Picks a field identifier for each property name.
+///
+/// Properties such as `id` and `$id` map onto the same identifier, in which
+/// case the name that is used verbatim wins and the renamed one gets a
+/// trailing underscore.
+fn field_idents<'s>(names: impl Iterator<Item = &'s String> + Clone) -> Vec<syn::Ident> {
+    let mut used = names
+        .clone()
+        .filter(|name| field_ident(name) == name)
+        .cloned()
+        .collect::<HashSet<_>>();
+    names
+        .map(|name| {
+            let mut ident = field_ident(name);
+            if ident != name {
+                while used.contains(&ident.to_string()) {
+                    ident = format_ident!("{}_", ident);
+                }
+                used.insert(ident.to_string());
+            }
+            ident
+        })
+        .collect()
+}
 
-    quote! {
-        #[serde(rename = #s)]
-        pub #field
+fn field(s: &str, ident: &syn::Ident) -> TokenStream {
+    if ident == s {
+        quote!( pub #ident )
+    } else {
+        quote! {
+            #[serde(rename = #s)]
+            pub #ident
+        }
     }
 }
 
@@ -181,12 +244,229 @@ where
     T: Clone,
 {
     *result = match (&mut result, r) {
-        (&mut &mut Some(ref mut result), &Some(ref r)) => return f(result, r),
-        (&mut &mut None, &Some(ref r)) => Some(r.clone()),
+        (&mut &mut Some(ref mut result), Some(r)) => return f(result, r),
+        (&mut &mut None, Some(r)) => Some(r.clone()),
         _ => return,
     };
 }
 
+/// Whether `schema` is the normalized form of the `false` schema.
+fn is_false_schema(schema: &Schema) -> bool {
+    schema
+        .not
+        .as_ref()
+        .map_or(false, |not| **not == Schema::default())
+}
+
+/// `unevaluatedProperties` acts like `additionalProperties` once `allOf` has
+/// been merged into a single schema.
+fn additional_properties(schema: &Schema) -> Option<Result<&Schema, bool>> {
+    match schema.additional_properties {
+        Some(ref additional) => Some(additional.as_schema()),
+        None => schema
+            .unevaluated_properties
+            .as_ref()
+            .map(|unevaluated| unevaluated.as_schema()),
+    }
+}
+
+/// The schema which is equivalent to the boolean schema `value`.
+fn bool_schema(value: bool) -> Schema {
+    if value {
+        Schema::default()
+    } else {
+        Schema {
+            not: Some(Box::default()),
+            ..Schema::default()
+        }
+    }
+}
+
+macro_rules! bool_or_schema {
+    ($($name:ident),*) => {$(
+        impl schema::$name {
+            /// The subschema, or the value of a `true` or `false` schema.
+            pub(crate) fn as_schema(&self) -> Result<&Schema, bool> {
+                match self {
+                    schema::$name::Variant0(value) => Err(*value),
+                    schema::$name::Variant1(schema) => Ok(schema),
+                }
+            }
+        }
+    )*};
+}
+
+bool_or_schema!(
+    SchemaAdditionalItems,
+    SchemaAdditionalProperties,
+    SchemaUnevaluatedItems,
+    SchemaUnevaluatedProperties
+);
+
+impl SchemaItems {
+    /// The schema of every item, or the schemas of the items by position if
+    /// `items` is an array. Even an array of one schema is such a tuple.
+    pub(crate) fn as_schema(&self) -> Result<&Schema, &[Schema]> {
+        match self {
+            SchemaItems::Variant0(tuple) => Err(tuple),
+            SchemaItems::Variant1(schema) => Ok(schema),
+        }
+    }
+}
+
+/// Adds the properties of `r` which `result` lacks as optional properties.
+///
+/// Used for subschemas which only apply under some condition, such as
+/// `dependentSchemas` or `then`.
+fn merge_optional_properties(result: &mut Schema, r: &Schema) {
+    for (k, v) in &r.properties {
+        result
+            .properties
+            .entry(k.clone())
+            .or_insert_with(|| v.clone());
+    }
+}
+
+/// Whether the type generated for `schema` is a plain value or a `Vec`, which
+/// can not lead back to the types containing it without indirection.
+fn is_simple(schema: &Schema) -> bool {
+    let simple_type = match schema.type_[..] {
+        [ref typ] => *typ != SimpleTypes::Object,
+        _ => false,
+    };
+    simple_type && schema.one_of.is_none() && schema.any_of.is_none() && schema.all_of.is_none()
+}
+
+/// Ranks how specific the type generated for `schema` is, that is how few
+/// values it accepts, for ordering the variants of untagged enums.
+fn specificity(schema: &Schema) -> (u8, usize) {
+    if schema.enum_.is_some() || schema.const_.is_some() {
+        return (5, 0);
+    }
+    let required = schema
+        .required
+        .as_ref()
+        .map_or(0, |required| required.len());
+    match schema.type_.first() {
+        Some(SimpleTypes::Null) | Some(SimpleTypes::Boolean) | Some(SimpleTypes::Integer) => (4, 0),
+        Some(SimpleTypes::Object) | None if additional_properties(schema) == Some(Err(false)) => {
+            (3, required)
+        }
+        Some(SimpleTypes::Object) | None if !schema.properties.is_empty() => (2, required),
+        Some(SimpleTypes::Number) | Some(SimpleTypes::String) | Some(SimpleTypes::Array) => (2, 0),
+        Some(SimpleTypes::Object) => (1, 0),
+        None => (0, 0),
+    }
+}
+
+/// The only value `schema` allows, if it is restricted to a single one through
+/// `const` or an `enum` without `enumNames`.
+fn constant(schema: &Schema) -> Option<&Value> {
+    match (&schema.const_, &schema.enum_) {
+        (Some(value), _) => Some(value),
+        (None, Some(values)) if schema.enum_names.is_none() => match &values[..] {
+            [value] => Some(value),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether `schema` is an `enum` of integers or of mixed values, which get an
+/// enum of their own when defined inline. Enums of strings are handled with
+/// the other strings and enums of booleans are simply `bool`.
+fn is_inline_enum(schema: &Schema) -> bool {
+    let values = match schema.enum_ {
+        Some(ref values) if schema.enum_names.is_none() => values,
+        _ => return false,
+    };
+    let mut values = values.iter().filter(|value| !value.is_null()).peekable();
+    values.peek().is_some()
+        && !values.clone().all(Value::is_string)
+        && !values.all(Value::is_boolean)
+}
+
+/// An expression of the Rust type `typ` for the `default` value of the field
+/// `field`.
+///
+/// Fails for a default which a primitive `typ` cannot hold, such as `5` for a
+/// `String` or `-1` for a field whose `minimum` and `maximum` make it a `u8`.
+/// Defaults of other types are deserialized when the expression is evaluated.
+fn default_expr(field: &str, typ: &str, default: &Value) -> Result<TokenStream, String> {
+    let is_integer = INTEGER_TYPES.contains(&typ);
+    Ok(match (typ, default) {
+        ("String", Value::String(s)) => quote!(#s.to_owned()),
+        ("bool", Value::Bool(b)) => quote!(#b),
+        (_, Value::Number(n)) if is_integer && n.is_i64() && integer_fits(typ, n) => {
+            let n = proc_macro2::Literal::i64_unsuffixed(n.as_i64().unwrap());
+            quote!(#n)
+        }
+        (_, Value::Number(n)) if is_integer && n.is_u64() && integer_fits(typ, n) => {
+            let n = proc_macro2::Literal::u64_unsuffixed(n.as_u64().unwrap());
+            quote!(#n)
+        }
+        ("f64", Value::Number(n)) => {
+            let n = proc_macro2::Literal::f64_suffixed(n.as_f64().unwrap());
+            quote!(#n)
+        }
+        ("f32", Value::Number(n)) => {
+            let n = proc_macro2::Literal::f32_suffixed(n.as_f64().unwrap() as f32);
+            quote!(#n)
+        }
+        _ if is_integer || ["String", "bool", "f64", "f32"].contains(&typ) => {
+            return Err(format!(
+                "The default `{}` is not a value of `{}`",
+                default, typ
+            ));
+        }
+        _ => {
+            let json = default.to_string();
+            let message = format!("Invalid default value of `{}`: {{}}", field);
+            quote!(serde_json::from_str(#json).unwrap_or_else(|err| panic!(#message, err)))
+        }
+    })
+}
+
+/// Names the variant of a generated enum which stands for the `enum` value
+/// `value`, for values other than strings.
+fn value_variant_name(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => "True".into(),
+        Value::Bool(false) => "False".into(),
+        Value::Number(n) => {
+            let n = n.to_string().replace('-', "Minus");
+            replace_invalid_identifier_chars(&format!("V{}", n))
+        }
+        Value::String(s) => str_to_ident(&s.to_pascal_case()).to_string(),
+        _ => "Value".into(),
+    }
+}
+
+/// Orders the types of a multi-type schema, the ones accepting fewer values
+/// first.
+fn type_specificity(typ: &SimpleTypes) -> u8 {
+    match typ {
+        SimpleTypes::Null => 0,
+        SimpleTypes::Boolean => 1,
+        SimpleTypes::Integer => 2,
+        SimpleTypes::Number => 3,
+        SimpleTypes::String => 4,
+        SimpleTypes::Array => 5,
+        SimpleTypes::Object => 6,
+    }
+}
+
+/// Returns the keyword and the schemas of a `oneOf` or `anyOf` which comes with
+/// an OpenAPI `discriminator`.
+fn discriminated_union(schema: &Schema) -> Option<(&'static str, &[Schema])> {
+    schema.discriminator.as_ref()?;
+    match (&schema.one_of, &schema.any_of) {
+        (Some(one_of), _) if !one_of.is_empty() => Some(("oneOf", one_of)),
+        (_, Some(any_of)) if !any_of.is_empty() => Some(("anyOf", any_of)),
+        _ => None,
+    }
+}
+
 fn merge_all_of(result: &mut Schema, r: &Schema) {
     use std::collections::btree_map::Entry;
 
@@ -211,9 +491,63 @@ fn merge_all_of(result: &mut Schema, r: &Schema) {
         required.extend(r_required.iter().cloned());
     });
 
+    // Only values allowed by both schemas remain
+    merge_option(&mut result.enum_, &r.enum_, |values, r_values| {
+        values.retain(|value| r_values.contains(value));
+    });
+    if r.const_.is_some() {
+        result.const_.clone_from(&r.const_);
+    }
+
     result.type_.retain(|e| r.type_.contains(e));
 }
 
+/// Looks up the subschema that `fragment` (taken from the reference `s`)
+/// points to within `document`.
+fn subschema<'s>(document: &'s Schema, fragment: &str, s: &str) -> Result<Cow<'s, Schema>, String> {
+    match pointer::tokens(fragment) {
+        Some(tokens) => pointer::resolve(document, &tokens)
+            .ok_or_else(|| format!("Unresolvable reference: `{}`", s)),
+        // Not a JSON pointer, try the fragment as the name of a definition
+        None => document
+            .definitions
+            .get(fragment)
+            .or_else(|| document.defs.get(fragment))
+            .map(Cow::Borrowed)
+            .ok_or_else(|| format!("Expected definition: `{}` {}", s, fragment)),
+    }
+}
+
+/// The name of the Rust type generated for a schema named `name`.
+fn type_ident_name(name: &str) -> String {
+    let name = replace_invalid_identifier_chars(&name.to_pascal_case());
+    replace_numeric_start(&name)
+}
+
+/// The name of the document at `uri`, its file name without the extension.
+fn document_stem(uri: &Uri) -> String {
+    let path = uri.path().to_string();
+    let file = path.rsplit('/').next().unwrap_or_default();
+    file.split('.').next().unwrap_or_default().to_owned()
+}
+
+/// The name of the variant of an untagged enum for the `i`th schema of a
+/// union: the last segment of its `$id` (or `id`) without the extension, such
+/// as `Point` for `http://example.com/point.json`, or else `Variant{i}`.
+fn variant_name(schema: &Schema, i: usize, used: &HashSet<String>) -> String {
+    schema
+        .id_
+        .as_ref()
+        .or(schema.id.as_ref())
+        .and_then(|id| {
+            let segment = id.trim_end_matches(['#', '/']).rsplit(['/', '#']).next()?;
+            let stem = segment.split('.').next()?;
+            Some(type_ident_name(stem))
+        })
+        .filter(|name| syn::parse_str::<syn::Ident>(name).is_ok() && !used.contains(name))
+        .unwrap_or_else(|| format!("Variant{}", i))
+}
+
 const LINE_LENGTH: usize = 100;
 const INDENT_LENGTH: usize = 4;
 
@@ -256,33 +590,143 @@ fn make_doc_comment(mut comment: &str, remaining_line: usize) -> TokenStream {
 
 struct FieldExpander<'a, 'r: 'a> {
     default: bool,
+    /// The checks of the fields' constraints, if validation is enabled
+    validations: Vec<TokenStream>,
+    /// Whether each field holds a value, for counting the properties
+    present: Vec<TokenStream>,
+    /// The associated functions of the struct, returning the `default` values
+    /// of the fields or deserializing them
+    fns: Vec<TokenStream>,
+    /// The initializers of the fields in the `Default` impl of the struct
+    default_fields: Vec<TokenStream>,
     expander: &'a mut Expander<'r>,
 }
 
 impl<'a, 'r> FieldExpander<'a, 'r> {
-    fn expand_fields(&mut self, type_name: &str, schema: &Schema) -> Vec<TokenStream> {
-        let schema = self.expander.schema(schema);
-        schema
+    fn expand_fields(
+        &mut self,
+        type_name: &str,
+        schema: &Schema,
+    ) -> Result<Vec<TokenStream>, Error> {
+        let target = match schema.ref_ {
+            Some(ref ref_) => Some(self.expander.ref_location(ref_)?),
+            None => None,
+        };
+        let mut schema = self.expander.schema(schema)?;
+        // The properties of a referenced schema are expanded at its location
+        let saved_location = target.map(|target| self.expander.location.replace(target));
+        let conditional_schemas = schema
+            .dependent_schemas
+            .values()
+            .chain(schema.then.as_deref())
+            .chain(schema.else_.as_deref())
+            .map(|conditional| Ok(self.expander.schema(conditional)?.into_owned()))
+            .collect::<Result<Vec<_>, Error>>()?;
+        for conditional in &conditional_schemas {
+            merge_optional_properties(schema.to_mut(), conditional);
+        }
+        let struct_name = replace_invalid_identifier_chars(&type_name.to_pascal_case());
+        let idents = field_idents(schema.properties.keys());
+        let used = idents.iter().map(|ident| ident.to_string()).collect();
+        let mut fields = schema
             .properties
             .iter()
-            .map(|(field_name, value)| {
+            .zip(idents)
+            .map(|((field_name, value), ident)| {
                 self.expander.current_field.clone_from(field_name);
-                let key = field(field_name);
+                let key = field(field_name, &ident);
+                // Read and write only properties are absent in one direction
                 let required = schema
                     .required
                     .iter()
                     .flat_map(|a| a.iter())
-                    .any(|req| req == field_name);
-                let field_type = self.expander.expand_type(type_name, required, value);
-                if !field_type.typ.starts_with("Option<") {
+                    .any(|req| req == field_name)
+                    && !value.read_only
+                    && !value.write_only;
+                let (field_type, checks, default_value) =
+                    self.expander
+                        .descend(&["properties", field_name], |expander| {
+                            let default_value = match value.default {
+                                Some(ref default) => Some(default.clone()),
+                                None => expander.schema(value)?.default.clone(),
+                            }
+                            // An empty object stands for a missing value rather
+                            // than one to build, which could nest without end in
+                            // a recursive type
+                            .filter(|default| {
+                                !default.is_null() && default != &Value::Object(Default::default())
+                            });
+                            // A property with a default value is never missing
+                            let field_type = expander.expand_type(
+                                type_name,
+                                required || default_value.is_some(),
+                                value,
+                            )?;
+                            let checks = expander.validation_checks(value)?;
+                            // Empty arrays are the default anyway
+                            let default_value = default_value
+                                .filter(|default| {
+                                    !(field_type.default && default == &Value::Array(Vec::new()))
+                                })
+                                .map(|default| default_expr(field_name, &field_type.typ, &default))
+                                .transpose()
+                                .map_err(|message| expander.located(Error::new(message)))?;
+                            Ok((field_type, checks, default_value))
+                        })?;
+                let optional = field_type.typ.starts_with("Option<");
+                if !optional && !field_type.default && default_value.is_none() {
                     self.default = false;
                 }
+                let default_fn = default_value.map(|value| {
+                    let fn_name =
+                        format_ident!("default_{}", ident.to_string().trim_start_matches("r#"));
+                    let typ = field_type.typ.parse::<TokenStream>().unwrap();
+                    self.fns.push(quote! {
+                        pub fn #fn_name() -> #typ {
+                            #value
+                        }
+                    });
+                    self.default_fields.push(quote!(#ident: Self::#fn_name()));
+                    format!("{}::{}", struct_name, fn_name)
+                });
+                if default_fn.is_none() {
+                    self.default_fields.push(quote!(#ident: Default::default()));
+                }
+                if self.expander.validate {
+                    let core = self.expander.schemafy_path();
+                    let checks = match checks {
+                        None => None,
+                        Some(checks) if optional => Some(quote! {
+                            if let Some(ref value) = self.#ident {
+                                #checks
+                            }
+                        }),
+                        Some(checks) => Some(quote! {
+                            let value = &self.#ident;
+                            #checks
+                        }),
+                    };
+                    self.validations.push(quote! {
+                        {
+                            let path = #core validate::join(path, #field_name);
+                            #checks
+                            #core validate::validate_field!(&self.#ident, &path, errors);
+                        }
+                    });
+                    self.present.push(if optional {
+                        quote!(usize::from(self.#ident.is_some()))
+                    } else {
+                        quote!(1)
+                    });
+                }
                 let typ = field_type.typ.parse::<TokenStream>().unwrap();
 
-                let default = if field_type.default {
-                    Some(quote! { #[serde(default)] })
-                } else {
-                    None
+                let default = match default_fn {
+                    Some(ref default_fn) if !required => {
+                        Some(quote! { #[serde(default = #default_fn)] })
+                    }
+                    _ if field_type.default => Some(quote! { #[serde(default)] }),
+                    _ => None,
                 };
                 let attributes = if field_type.attributes.is_empty() {
                     None
@@ -299,14 +743,202 @@ impl<'a, 'r> FieldExpander<'a, 'r> {
                     .description
                     .as_ref()
                     .map(|comment| make_doc_comment(comment, LINE_LENGTH - INDENT_LENGTH));
-                quote! {
+                Ok(quote! {
                     #comment
                     #default
                     #attributes
                     #key : #typ
-                }
+                })
             })
-            .collect()
+            .collect::<Result<Vec<_>, Error>>()?;
+        if let Some(extra) = self.expand_extra_field(type_name, &schema, used)? {
+            fields.push(extra);
+        }
+        if let Some(saved_location) = saved_location {
+            self.expander.location.replace(saved_location);
+        }
+        Ok(fields)
+    }
+
+    /// The field keeping the properties which `schema` does not declare, so
+    /// that they survive a round trip. They are typed after its
+    /// `patternProperties` and `additionalProperties`, through an untagged
+    /// enum if there are several of them. Untyped properties are only kept if
+    /// extra properties are enabled, or else the `patternProperties` would
+    /// reject them.
+    fn expand_extra_field(
+        &mut self,
+        type_name: &str,
+        schema: &Schema,
+        mut used: HashSet<String>,
+    ) -> Result<Option<TokenStream>, Error> {
+        if schema.properties.is_empty() && schema.pattern_properties.is_empty() {
+            return Ok(None);
+        }
+        // The schemas of the values along with the name of their variant and
+        // their location
+        let mut values = Vec::new();
+        for (i, (pattern, value)) in schema.pattern_properties.iter().enumerate() {
+            let resolved = self.expander.schema(value)?;
+            values.push((
+                specificity(&resolved),
+                format!("Pattern{}", i),
+                vec!["patternProperties", pattern.as_str()],
+                Some(value),
+            ));
+        }
+        // A stable sort keeps the order of the schema for equally specific ones
+        values.sort_by_key(|&(specificity, ..)| std::cmp::Reverse(specificity));
+        let mut values = values
+            .into_iter()
+            .map(|(_, variant, tokens, value)| (variant, tokens, value))
+            .collect::<Vec<_>>();
+        let keyword = if schema.additional_properties.is_some() {
+            "additionalProperties"
+        } else {
+            "unevaluatedProperties"
+        };
+        let additional = additional_properties(schema);
+        match additional {
+            Some(Err(false)) => (),
+            Some(Ok(additional)) if *additional != Schema::default() => {
+                values.push(("Additional".into(), vec![keyword], Some(additional)))
+            }
+            _ if self.expander.extra_properties || !values.is_empty() => {
+                values.push(("Additional".into(), Vec::new(), None))
+            }
+            _ => (),
+        }
+        if values.is_empty() {
+            return Ok(None);
+        }
+        let mut name = String::from("extra");
+        while !used.insert(name.clone()) {
+            name.push('_');
+        }
+        let ident = format_ident!("{}", name);
+        // Types defined inline are named after the field
+        self.expander.current_field = "extra".into();
+        let (typ, checks) = match values[..] {
+            [(_, ref tokens, Some(value))] => self.expander.descend(tokens, |expander| {
+                let typ = expander.expand_type(type_name, true, value)?.typ;
+                let checks = expander.validation_checks(value)?;
+                Ok::<_, Error>((typ, checks))
+            })?,
+            [(_, _, None)] => ("serde_json::Value".to_owned(), None),
+            _ => (self.expand_extra_enum(type_name, &values)?, None),
+        };
+        let typ = typ.parse::<TokenStream>().unwrap();
+        let core = self.expander.schemafy_path();
+        // Without additional properties, every property which the schema does
+        // not declare must match one of the patterns
+        let patterns = schema
+            .pattern_properties
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let closed = !patterns.is_empty() && additional == Some(Err(false));
+        let deserialize_with = if closed && self.expander.validating_newtypes {
+            let fn_name = format_ident!("deserialize_{}", name);
+            self.fns.push(quote! {
+                fn #fn_name<'de, D>(
+                    deserializer: D,
+                ) -> Result<::std::collections::BTreeMap<String, #typ>, D::Error>
+                where
+                    D: #core serde::Deserializer<'de>,
+                {
+                    #core validate::deserialize_pattern_properties(deserializer, &[#(#patterns),*])
+                }
+            });
+            let struct_name = replace_invalid_identifier_chars(&type_name.to_pascal_case());
+            let path = format!("{}::{}", struct_name, fn_name);
+            Some(quote!(, deserialize_with = #path))
+        } else {
+            None
+        };
+        self.default_fields.push(quote!(#ident: Default::default()));
+        if self.expander.validate {
+            let checks = checks.map(|checks| {
+                quote! {
+                    for (key, value) in self.#ident.iter() {
+                        let path = #core validate::join(path, key);
+                        #checks
+                    }
+                }
+            });
+            let key_checks = if closed && !self.expander.validating_newtypes {
+                Some(quote! {
+                    #core validate::pattern_properties(
+                        self.#ident.keys(),
+                        &[#(#patterns),*],
+                        path,
+                        errors,
+                    );
+                })
+            } else {
+                None
+            };
+            self.validations.push(quote! {
+                #key_checks
+                #checks
+                #core validate::validate_field!(&self.#ident, path, errors);
+            });
+            self.present.push(quote!(self.#ident.len()));
+        }
+        Ok(Some(quote! {
+            /// The properties which the schema does not declare
+            #[serde(flatten #deserialize_with)]
+            pub #ident: ::std::collections::BTreeMap<String, #typ>
+        }))
+    }
+
+    /// Generates the untagged enum of the properties which a schema does not
+    /// declare, with a variant for each of the `values` given by
+    /// [`expand_extra_field`](FieldExpander::expand_extra_field).
+    fn expand_extra_enum(
+        &mut self,
+        type_name: &str,
+        values: &[(String, Vec<&str>, Option<&Schema>)],
+    ) -> Result<String, Error> {
+        let enum_name = self.expander.inline_type_name();
+        let mut variant_names = Vec::with_capacity(values.len());
+        let mut variant_types = Vec::with_capacity(values.len());
+        for (variant, tokens, value) in values {
+            let typ = match value {
+                Some(value) => {
+                    // Types defined inline are named after the variant
+                    self.expander.current_field = format!("extra_{}", variant);
+                    self.expander
+                        .descend(tokens, |expander| {
+                            expander.expand_type(type_name, true, value)
+                        })?
+                        .typ
+                }
+                None => "serde_json::Value".to_owned(),
+            };
+            variant_names.push(format_ident!("{}", variant));
+            variant_types.push(typ.parse::<TokenStream>().unwrap());
+        }
+        let enum_ident = format_ident!("{}", enum_name);
+        let validate = self.expander.validate_variants(
+            &enum_ident,
+            &variant_names
+                .iter()
+                .map(|name| (name.clone(), true))
+                .collect::<Vec<_>>(),
+        );
+        self.expander.types.push((
+            enum_name.clone(),
+            quote! {
+                #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+                #[serde(untagged)]
+                pub enum #enum_ident {
+                    #(#variant_names(#variant_types)),*
+                }
+                #validate
+            },
+        ));
+        Ok(enum_name)
     }
 }
 
@@ -314,6 +946,41 @@ pub struct Expander<'r> {
     root_name: Option<&'r str>,
     schemafy_path: &'r str,
     root: &'r Schema,
+    /// The URI of the root schema, relative references are resolved against it
+    base_uri: Option<Uri>,
+    /// The document (`None` for the root schema) and the reference tokens of
+    /// the schema currently being expanded
+    location: RefCell<resolver::Location>,
+    /// Provides the documents referenced from the root schema
+    resolver: &'r dyn Resolver,
+    /// Whether to implement `Validate` for the generated types
+    validate: bool,
+    /// Whether to generate newtypes enforcing the constraints of primitive
+    /// types while deserializing
+    validating_newtypes: bool,
+    /// Whether integers without an upper bound which cannot be negative are
+    /// generated as `u64`
+    unsigned_integers: bool,
+    /// Whether structs keep the properties they do not declare even if
+    /// `additionalProperties` does not give them a type
+    extra_properties: bool,
+    /// The Rust types of strings by their `format`
+    formats: BTreeMap<String, String>,
+    /// The Rust types of schemas by their JSON pointer in the root schema
+    type_overrides: BTreeMap<String, String>,
+    /// Documents other than the root schema which have been loaded through `$ref`
+    documents: RefCell<HashMap<Uri, Schema>>,
+    /// The schemas in the loaded documents which can be referenced by `id` or
+    /// by anchor
+    index: RefCell<resolver::Index>,
+    /// Referenced types which are not generated as part of a definition, along
+    /// with their document and fragment
+    pending: Vec<(String, Option<Uri>, String)>,
+    /// The location of the schema which each type name has been given to, so
+    /// that schemas of the same name in different places get different types
+    type_names: HashMap<String, resolver::Location>,
+    /// The locations of the schemas whose types have been generated
+    generated: HashSet<resolver::Location>,
     current_type: String,
     current_field: String,
     types: Vec<(String, TokenStream)>,
@@ -348,73 +1015,394 @@ impl<'r> Expander<'r> {
             root_name,
             root,
             schemafy_path,
+            base_uri: None,
+            location: RefCell::default(),
+            resolver: &FileResolver,
+            validate: false,
+            validating_newtypes: false,
+            unsigned_integers: false,
+            extra_properties: false,
+            formats: default_formats(schemafy_path),
+            type_overrides: BTreeMap::new(),
+            documents: RefCell::default(),
+            index: RefCell::default(),
+            pending: Vec::new(),
+            type_names: HashMap::new(),
+            generated: HashSet::new(),
             current_field: "".into(),
             current_type: "".into(),
             types: Vec::new(),
         }
     }
 
+    /// Sets the URI of the root schema, which relative references to other
+    /// schema documents are resolved against. Defaults to the current
+    /// directory.
+    pub fn with_base_uri(mut self, base_uri: Uri) -> Self {
+        self.base_uri = Some(base_uri);
+        self
+    }
+
+    /// Sets the resolver which provides the documents referenced from the root
+    /// schema. Defaults to [`FileResolver`].
+    pub fn with_resolver(mut self, resolver: &'r dyn Resolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Sets whether the generated types check the constraints which serde does
+    /// not enforce, such as `minimum` or `pattern`, in a `validate` method.
+    /// This needs the `validate` feature of `schemafy_core`. Defaults to
+    /// `false`.
+    pub fn with_validate(mut self, validate: bool) -> Self {
+        self.validate = validate;
+        self
+    }
+
+    /// Sets whether numbers, strings, arrays and maps with constraints, such
+    /// as `minimum` or `pattern`, are generated as newtypes which can only be
+    /// deserialized from, or converted from, values meeting them. This needs
+    /// the `validate` feature of `schemafy_core`. Defaults to `false`.
+    pub fn with_validating_newtypes(mut self, validating_newtypes: bool) -> Self {
+        self.validating_newtypes = validating_newtypes;
+        self
+    }
+
+    /// Sets whether integers which cannot be negative, through `minimum`, are
+    /// generated as `u64` rather than `i64` if they have no upper bound.
+    /// Integers with both bounds always get the smallest type holding them.
+    /// Defaults to `false`.
+    pub fn with_unsigned_integers(mut self, unsigned_integers: bool) -> Self {
+        self.unsigned_integers = unsigned_integers;
+        self
+    }
+
+    /// Sets whether structs keep the properties which their schema does not
+    /// declare, as `serde_json::Value`s in an `extra` map, if their
+    /// `additionalProperties` is `true` or missing. Properties which
+    /// `additionalProperties` gives a schema are always kept, as values of
+    /// its type. Defaults to `false`.
+    pub fn with_extra_properties(mut self, extra_properties: bool) -> Self {
+        self.extra_properties = extra_properties;
+        self
+    }
+
+    /// Generates the Rust type `typ` for strings of the given `format`, such as
+    /// `"date-time"`, instead of the type enabled through the features of this
+    /// crate, if any. `typ` needs to deserialize from, and serialize to, the
+    /// string. Mapping a format to `String` turns its mapping off.
+    pub fn with_format(mut self, format: &str, typ: &str) -> Self {
+        self.formats.insert(format.to_owned(), typ.to_owned());
+        self
+    }
+
+    /// Generates the Rust type `typ` for the schema at the JSON pointer
+    /// `pointer` within the root schema, such as `/properties/amount` for a
+    /// property or `/definitions/Id` for a definition, instead of the type it
+    /// would get. `typ` needs to deserialize from, and serialize to, the JSON
+    /// values of the schema, such as `serde_json::Number` for numbers which
+    /// overflow `i64` and `f64`. Its constraints are not checked.
+    pub fn with_type(mut self, pointer: &str, typ: &str) -> Self {
+        self.type_overrides
+            .insert(pointer.to_owned(), typ.to_owned());
+        self
+    }
+
+    /// The files the schema documents were read from so far: the root schema,
+    /// if its base URI is a `file` URI, and every file loaded through `$ref`.
+    ///
+    /// Useful to rebuild the generated code whenever one of them changes.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let documents = self.documents.borrow();
+        let mut files = self
+            .base_uri
+            .iter()
+            .chain(documents.keys())
+            .filter_map(resolver::uri_path)
+            .filter(|path| path.is_file())
+            .collect::<Vec<_>>();
+        files.sort();
+        files.dedup();
+        files
+    }
+
+    fn root_uri(&self) -> Cow<'_, Uri> {
+        match self.base_uri {
+            Some(ref uri) => Cow::Borrowed(uri),
+            None => Cow::Owned(resolver::current_dir_uri()),
+        }
+    }
+
+    /// Runs `f` on the subschema at `tokens` below the current location.
+    fn descend<T>(&mut self, tokens: &[&str], f: impl FnOnce(&mut Self) -> T) -> T {
+        let len = self.location.borrow().1.len();
+        self.location
+            .borrow_mut()
+            .1
+            .extend(tokens.iter().map(|&token| token.to_owned()));
+        let result = f(self);
+        self.location.borrow_mut().1.truncate(len);
+        result
+    }
+
+    /// Runs `f` on the schema at `location`, such as the target of a reference.
+    fn at<T>(&mut self, location: resolver::Location, f: impl FnOnce(&mut Self) -> T) -> T {
+        let saved_location = self.location.replace(location);
+        let result = f(self);
+        self.location.replace(saved_location);
+        result
+    }
+
+    /// Records the current location in `error`, unless it already has one.
+    fn located(&self, error: Error) -> Error {
+        let (document, tokens) = &*self.location.borrow();
+        let document = match document {
+            Some(uri) => Cow::Borrowed(uri),
+            None => self.root_uri(),
+        };
+        error.at(&document, &pointer::pointer(tokens))
+    }
+
+    /// The base URI that references are currently resolved against.
+    fn scope(&self) -> Result<Uri, Error> {
+        let (document, tokens) = self.location.borrow().clone();
+        self.scope_of(&document, &tokens)
+    }
+
+    /// Returns the index of the schemas which can be referenced by `id` or by
+    /// anchor, indexing the root schema on first use.
+    fn index(&self) -> RefMut<'_, resolver::Index> {
+        let mut index = self.index.borrow_mut();
+        if index.is_empty() {
+            index.add_document(None, self.root_uri().into_owned(), self.root);
+        }
+        index
+    }
+
+    /// Splits a reference into the document it points into (`None` for the
+    /// root schema) and the fragment within that document.
+    fn locate(&self, s: &str) -> Result<(Option<Uri>, String), Error> {
+        let scope = self.scope()?;
+        let (uri, fragment) = resolver::resolve_reference(&scope, s).unwrap_or_else(|| {
+            // ref is supposed to be be a valid URI, however we should better have a fallback plan
+            let fragment = s.split_once('#').map_or(s, |(_, fragment)| fragment);
+            (scope.clone(), fragment.to_owned())
+        });
+        let resource = self.index().resource(&uri).cloned();
+        let is_pointer = pointer::tokens(&fragment).is_some();
+        if !is_pointer {
+            // Anchors are only known once their document is loaded
+            if resource.is_none() && self.document(uri.clone()).is_err() {
+                return Ok((Some(uri), fragment));
+            }
+            if let Some((document, tokens)) = self.index().anchor(&uri, &fragment) {
+                return Ok((document.clone(), pointer::fragment(tokens)));
+            }
+        }
+        Ok(match self.index().resource(&uri) {
+            Some((document, tokens)) if is_pointer => {
+                (document.clone(), pointer::fragment(tokens) + &fragment)
+            }
+            Some((document, _)) => (document.clone(), fragment),
+            None => (Some(uri), fragment),
+        })
+    }
+
+    /// Returns the base URI within the schema at `tokens` of `document`.
+    fn scope_of(&self, document: &Option<Uri>, tokens: &[String]) -> Result<Uri, Error> {
+        let mut scope = match document {
+            Some(uri) => uri.clone(),
+            None => self.root_uri().into_owned(),
+        };
+        let mut visit = |schema: &Schema| {
+            if let Some(uri) = resolver::scope(&scope, schema) {
+                scope = uri;
+            }
+        };
+        match document {
+            None => {
+                if let Some(target) = pointer::resolve_with(self.root, tokens, &mut visit) {
+                    visit(&target);
+                }
+            }
+            Some(uri) => {
+                let document = self.document(uri.clone())?;
+                if let Some(target) = pointer::resolve_with(&document, tokens, &mut visit) {
+                    visit(&target);
+                }
+            }
+        }
+        Ok(scope)
+    }
+
+    /// Returns the location of the schema that `s` refers to.
+    fn ref_location(&self, s: &str) -> Result<resolver::Location, Error> {
+        let (document, fragment) = self.locate(s)?;
+        Ok((document, pointer::tokens(&fragment).unwrap_or_default()))
+    }
+
+    /// Returns the name of the schema that `s` refers to, before it is turned
+    /// into a type name.
+    fn ref_name(&self, s: &str) -> Result<String, Error> {
+        let (document, fragment) = self.locate(s)?;
+
+        let document_name = match document {
+            None if fragment.is_empty() => self.root_name.ok_or_else(|| {
+                self.located(Error::new(format!(
+                    "No root name specified for schema, which `{}` refers to",
+                    s
+                )))
+            })?,
+            None => self.root_name.unwrap_or_default(),
+            // Another document is named after its file
+            Some(ref uri) => match s.split('#').next().unwrap_or(s) {
+                // A fragment-only ref within another document
+                "" => &document_stem(uri),
+                document => {
+                    let document = document.rsplit('/').next().unwrap_or(document);
+                    document.strip_suffix(".json").unwrap_or(document)
+                }
+            },
+        };
+        Ok(match pointer::tokens(&fragment) {
+            Some(tokens) => pointer::type_name(document_name, &tokens),
+            None => fragment.rsplit('/').next().unwrap_or_default().to_owned(),
+        })
+    }
+
+    #[cfg(test)]
     fn type_ref(&self, s: &str) -> String {
-        // ref is supposed to be be a valid URI, however we should better have a fallback plan
-        let fragment = URI::try_from(s)
-            .map(|uri| uri.fragment().map(Fragment::to_owned))
-            .ok()
-            .flatten()
-            .or({
-                let s = s.strip_prefix('#').unwrap_or(s);
-                Fragment::try_from(s).ok()
-            })
-            .map(|fragment| fragment.to_string())
-            .unwrap_or_else(|| s.to_owned());
+        type_ident_name(&self.ref_name(s).unwrap())
+    }
 
-        let ref_ = if fragment.is_empty() {
-            self.root_name.expect("No root name specified for schema")
-        } else {
-            fragment.split('/').last().expect("Component")
+    /// Returns the name of the type which `s` refers to, scheduling the type
+    /// to be generated unless it is a definition of the root schema.
+    fn referenced_type(&mut self, s: &str) -> Result<String, Error> {
+        let name = self.ref_name(s)?;
+        let (document, fragment) = self.locate(s)?;
+        // Report unresolvable references here rather than where they are generated
+        self.subschema(document.clone(), &fragment, s)?;
+        let location = (
+            document.clone(),
+            pointer::tokens(&fragment).unwrap_or_default(),
+        );
+        let type_name = self.claim_type_name(&name, location)?;
+        let is_definition = pointer::tokens(&fragment).map_or(true, |t| pointer::is_definition(&t));
+        if document.is_some() || !is_definition {
+            let name = if type_name == type_ident_name(&name) {
+                name
+            } else {
+                type_name.clone()
+            };
+            self.pending.push((name, document, fragment));
+        }
+        Ok(type_name)
+    }
+
+    /// Returns the name of the type of the schema at `location`, which is
+    /// named `name`. If another schema already has a type of that name, the
+    /// name of the document of the schema is prepended, such as
+    /// `CommonAddress` for `common.json#/definitions/Address`.
+    fn claim_type_name(
+        &mut self,
+        name: &str,
+        location: resolver::Location,
+    ) -> Result<String, Error> {
+        let document_name = match location.0 {
+            Some(ref uri) => document_stem(uri),
+            None => self.root_name.unwrap_or_default().to_owned(),
         };
+        let type_name = type_ident_name(name);
+        let candidates = [
+            type_name.clone(),
+            type_ident_name(&format!("{}_{}", document_name, name)),
+        ];
+        for candidate in candidates {
+            match self.type_names.get(&candidate) {
+                Some(claimed) if *claimed != location => continue,
+                Some(_) => (),
+                None => {
+                    self.type_names.insert(candidate.clone(), location);
+                }
+            }
+            return Ok(candidate);
+        }
+        Err(self.located(Error::new(format!(
+            "The type name `{}` is already given to another schema",
+            type_name
+        ))))
+    }
 
-        let ref_ = ref_.to_pascal_case();
-        let ref_ = replace_invalid_identifier_chars(&ref_);
-        replace_numeric_start(&ref_)
+    /// Returns the document identified by `uri`, loading it on first use.
+    fn document(&self, uri: Uri) -> Result<Ref<'_, Schema>, Error> {
+        if !self.documents.borrow().contains_key(&uri) {
+            let schema = resolver::load_document(self.resolver, &uri, &resolver::display_uri(&uri))
+                .map_err(|err| self.located(err))?;
+            self.index()
+                .add_document(Some(uri.clone()), uri.clone(), &schema);
+            self.documents.borrow_mut().insert(uri.clone(), schema);
+        }
+        Ok(Ref::map(self.documents.borrow(), |documents| {
+            &documents[&uri]
+        }))
     }
 
-    fn schema(&self, schema: &'r Schema) -> Cow<'r, Schema> {
-        let schema = match schema.ref_ {
-            Some(ref ref_) => self.schema_ref(ref_),
-            None => schema,
+    fn schema<'s>(&self, schema: &'s Schema) -> Result<Cow<'s, Schema>, Error>
+    where
+        'r: 's,
+    {
+        let (schema, target) = match schema.ref_ {
+            Some(ref ref_) => (self.schema_ref(ref_)?, Some(self.ref_location(ref_)?)),
+            None => (Cow::Borrowed(schema), None),
         };
         match schema.all_of {
             Some(ref all_of) if !all_of.is_empty() => {
-                all_of
-                    .iter()
-                    .skip(1)
-                    .fold(self.schema(&all_of[0]).clone(), |mut result, def| {
-                        merge_all_of(result.to_mut(), &self.schema(def));
-                        result
-                    })
+                // The members of a referenced schema are resolved at its location
+                let saved_location = target.map(|target| self.location.replace(target));
+                let merged = all_of.iter().skip(1).try_fold(
+                    self.schema(&all_of[0])?.into_owned(),
+                    |mut result, def| {
+                        merge_all_of(&mut result, &*self.schema(def)?);
+                        Ok(result)
+                    },
+                );
+                if let Some(saved_location) = saved_location {
+                    self.location.replace(saved_location);
+                }
+                Ok(Cow::Owned(merged?))
             }
-            _ => Cow::Borrowed(schema),
+            _ => Ok(schema),
         }
     }
 
-    fn schema_ref(&self, s: &str) -> &'r Schema {
-        s.split('/').fold(self.root, |schema, comp| {
-            if comp.ends_with('#') {
-                self.root
-            } else if comp == "definitions" {
-                schema
-            } else {
-                schema
-                    .definitions
-                    .get(comp)
-                    .unwrap_or_else(|| panic!("Expected definition: `{}` {}", s, comp))
-            }
-        })
+    fn schema_ref(&self, s: &str) -> Result<Cow<'r, Schema>, Error> {
+        let (document, fragment) = self.locate(s)?;
+        self.subschema(document, &fragment, s)
     }
 
-    fn expand_type(&mut self, type_name: &str, required: bool, typ: &Schema) -> FieldType {
+    /// Looks up `fragment` in `document` (the root schema if `None`).
+    fn subschema(
+        &self,
+        document: Option<Uri>,
+        fragment: &str,
+        s: &str,
+    ) -> Result<Cow<'r, Schema>, Error> {
+        match document {
+            None => subschema(self.root, fragment, s),
+            Some(document) => subschema(&*self.document(document)?, fragment, s)
+                .map(|schema| Cow::Owned(schema.into_owned())),
+        }
+        .map_err(|message| self.located(Error::new(message)))
+    }
+
+    fn expand_type(
+        &mut self,
+        type_name: &str,
+        required: bool,
+        typ: &Schema,
+    ) -> Result<FieldType, Error> {
         let saved_type = self.current_type.clone();
-        let mut result = self.expand_type_(typ);
+        let mut result = self.expand_type_(typ)?;
         self.current_type = saved_type;
         if type_name.to_pascal_case() == result.typ.to_pascal_case() {
             result.typ = format!("Box<{}>", result.typ)
@@ -429,80 +1417,106 @@ impl<'r> Expander<'r> {
                     .push("skip_serializing_if=\"Option::is_none\"".into());
             }
         }
-        result
+        Ok(result)
     }
 
-    fn expand_type_(&mut self, typ: &Schema) -> FieldType {
-        if let Some(ref ref_) = typ.ref_ {
-            self.type_ref(ref_).into()
+    fn expand_type_(&mut self, typ: &Schema) -> Result<FieldType, Error> {
+        Ok(if let Some(typ) = self.type_override() {
+            typ.into()
+        } else if let Some(ref ref_) = typ.ref_ {
+            self.referenced_type(ref_)?.into()
+        } else if let Some(value) = constant(typ) {
+            let name = self.inline_type_name();
+            let tokens = self.expand_constant(&format_ident!("{}", name), value);
+            self.types.push((name.clone(), tokens));
+            name.into()
+        } else if let Some((keyword, schemas)) = discriminated_union(typ) {
+            let (type_name, type_def) =
+                self.expand_one_of(keyword, schemas, typ.discriminator.as_ref())?;
+            self.types.push((type_name.clone(), type_def));
+            type_name.into()
         } else if typ.any_of.as_ref().map_or(false, |a| a.len() >= 2) {
             let any_of = typ.any_of.as_ref().unwrap();
-            let simple = self.schema(&any_of[0]);
-            let array = self.schema(&any_of[1]);
-            if !array.type_.is_empty() {
-                if let SimpleTypes::Array = array.type_[0] {
-                    if simple == self.schema(&array.items[0]) {
-                        return FieldType {
-                            typ: format!("Vec<{}>", self.expand_type_(&any_of[0]).typ),
+            let simple = self.schema(&any_of[0])?;
+            let array = self.schema(&any_of[1])?;
+            if array.type_.first() == Some(&SimpleTypes::Array) {
+                if let Some(Ok(item)) = array.items.as_ref().map(|items| items.as_schema()) {
+                    if any_of.len() == 2 && simple == self.schema(item)? {
+                        let item_type =
+                            self.descend(&["anyOf", "0"], |this| this.expand_type_(&any_of[0]))?;
+                        return Ok(FieldType {
+                            typ: format!("Vec<{}>", item_type.typ),
                             attributes: vec![format!(
                                 r#"with="{}one_or_many""#,
                                 self.schemafy_path
                             )],
                             default: true,
-                        };
+                        });
                     }
                 }
             }
-            "serde_json::Value".into()
+            self.expand_any_of(any_of)?
         } else if typ.one_of.as_ref().map_or(false, |a| a.len() >= 2) {
             let schemas = typ.one_of.as_ref().unwrap();
-            let (type_name, type_def) = self.expand_one_of(schemas);
+            let (type_name, type_def) = self.expand_one_of("oneOf", schemas, None)?;
             self.types.push((type_name.clone(), type_def));
             type_name.into()
-        } else if typ.type_.len() == 2 {
-            if typ.type_[0] == SimpleTypes::Null || typ.type_[1] == SimpleTypes::Null {
-                let mut ty = typ.clone();
-                ty.type_.retain(|x| *x != SimpleTypes::Null);
-
-                FieldType {
-                    typ: format!("Option<{}>", self.expand_type_(&ty).typ),
-                    attributes: vec![],
-                    default: true,
-                }
-            } else {
-                "serde_json::Value".into()
-            }
+        } else if is_inline_enum(typ) {
+            let name = self.inline_type_name();
+            let tokens = self.expand_schema(&name, typ)?;
+            self.types.push((name.clone(), tokens));
+            name.into()
+        } else if typ.type_.len() >= 2 {
+            self.expand_multi_type(typ)?
+        } else if let Some(checks) = self.newtype_checks(typ) {
+            let name = self.inline_type_name();
+            let tokens = self.expand_newtype(&format_ident!("{}", name), typ, checks)?;
+            self.types.push((name.clone(), tokens));
+            name.into()
         } else if typ.type_.len() == 1 {
             match typ.type_[0] {
-                SimpleTypes::String => {
-                    if typ.enum_.as_ref().map_or(false, |e| e.is_empty()) {
-                        "serde_json::Value".into()
-                    } else {
-                        "String".into()
+                SimpleTypes::String => match typ.enum_ {
+                    Some(ref enum_) if enum_.is_empty() => "serde_json::Value".into(),
+                    // Handle enums defined inline
+                    Some(_) => {
+                        let name = self.inline_type_name();
+                        let tokens = self.expand_schema(&name, typ)?;
+                        self.types.push((name.clone(), tokens));
+                        name.into()
                     }
+                    None => self
+                        .format_type(typ)
+                        .unwrap_or_else(|| "String".into())
+                        .into(),
+                },
+                SimpleTypes::Integer | SimpleTypes::Number if self.format_type(typ).is_some() => {
+                    self.format_type(typ).unwrap().into()
                 }
-                SimpleTypes::Integer => "i64".into(),
+                SimpleTypes::Integer => self.integer_type(typ).into(),
                 SimpleTypes::Boolean => "bool".into(),
+                SimpleTypes::Number if typ.format.as_deref() == Some("float") => "f32".into(),
                 SimpleTypes::Number => "f64".into(),
                 // Handle objects defined inline
                 SimpleTypes::Object
                     if !typ.properties.is_empty()
-                        || typ.additional_properties == Some(Value::Bool(false)) =>
+                        || !typ.pattern_properties.is_empty()
+                        || additional_properties(typ) == Some(Err(false)) =>
                 {
-                    let name = format!(
-                        "{}{}",
-                        self.current_type.to_pascal_case(),
-                        self.current_field.to_pascal_case()
-                    );
-                    let tokens = self.expand_schema(&name, typ);
+                    let name = self.inline_type_name();
+                    let tokens = self.expand_schema(&name, typ)?;
                     self.types.push((name.clone(), tokens));
                     name.into()
                 }
                 SimpleTypes::Object => {
-                    let prop = match typ.additional_properties {
-                        Some(ref props) if props.is_object() => {
-                            let prop = serde_json::from_value(props.clone()).unwrap();
-                            self.expand_type_(&prop).typ
+                    let keyword = if typ.additional_properties.is_some() {
+                        "additionalProperties"
+                    } else {
+                        "unevaluatedProperties"
+                    };
+                    let prop = match additional_properties(typ) {
+                        Some(Ok(prop)) => {
+                            self.descend(&[keyword], |this| this.expand_type_(prop))?
+                                .typ
                         }
                         _ => "serde_json::Value".into(),
                     };
@@ -513,21 +1527,71 @@ impl<'r> Expander<'r> {
                         default: typ.default == Some(Value::Object(Default::default())),
                     }
                 }
+                SimpleTypes::Array if typ.prefix_items.is_some() => self.expand_tuple(typ)?,
                 SimpleTypes::Array => {
-                    let item_type = typ.items.get(0).map_or("serde_json::Value".into(), |item| {
-                        self.current_type = format!("{}Item", self.current_type);
-                        self.expand_type_(item).typ
-                    });
+                    // Only the first schema of a tuple is used
+                    let item = match typ.items.as_ref().map(|items| items.as_schema()) {
+                        Some(Ok(item)) => Some((&["items"][..], item)),
+                        Some(Err(tuple)) => tuple.first().map(|item| (&["items", "0"][..], item)),
+                        None => None,
+                    };
+                    let item_type = match item {
+                        Some((tokens, item)) => {
+                            self.current_type = format!("{}Item", self.current_type);
+                            self.descend(tokens, |this| this.expand_type_(item))?.typ
+                        }
+                        None => "serde_json::Value".into(),
+                    };
                     format!("Vec<{}>", item_type).into()
                 }
                 _ => "serde_json::Value".into(),
             }
         } else {
             "serde_json::Value".into()
+        })
+    }
+
+    /// `prefixItems` describe a tuple if no further items are allowed,
+    /// otherwise the items can only be typed as `serde_json::Value`.
+    fn expand_tuple(&mut self, typ: &Schema) -> Result<FieldType, Error> {
+        let prefix_items = typ.prefix_items.as_deref().unwrap_or_default();
+        let closed = typ
+            .items
+            .as_ref()
+            .and_then(|items| items.as_schema().ok())
+            .map_or(false, is_false_schema)
+            || typ.unevaluated_items == Some(SchemaUnevaluatedItems::Variant0(false))
+            || typ.max_items == Some(prefix_items.len() as i64);
+        if !closed {
+            return Ok("Vec<serde_json::Value>".into());
         }
+        let saved_type = self.current_type.clone();
+        let item_types = prefix_items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| {
+                self.current_type = format!("{}Item{}", saved_type, i);
+                self.descend(&["prefixItems", &i.to_string()], |this| {
+                    Ok(this.expand_type_(item)?.typ)
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.current_type = saved_type;
+        Ok(format!("({},)", item_types.join(", ")).into())
+    }
+
+    /// The name of a type generated for a schema defined inline in the
+    /// current field.
+    fn inline_type_name(&self) -> String {
+        format!(
+            "{}{}",
+            self.current_type.to_pascal_case(),
+            self.current_field.to_pascal_case()
+        )
     }
 
-    fn expand_one_of(&mut self, schemas: &[Schema]) -> (String, TokenStream) {
+    /// The name of the enum generated for a union in the current field.
+    fn union_type_name(&self) -> String {
         let current_field = if self.current_field.is_empty() {
             "".to_owned()
         } else {
@@ -535,70 +1599,667 @@ impl<'r> Expander<'r> {
                 .to_string()
                 .to_pascal_case()
         };
-        let saved_type = format!("{}{}", self.current_type, current_field);
+        format!("{}{}", self.current_type, current_field)
+    }
+
+    fn expand_one_of(
+        &mut self,
+        keyword: &str,
+        schemas: &[Schema],
+        discriminator: Option<&Discriminator>,
+    ) -> Result<(String, TokenStream), Error> {
+        let saved_type = self.union_type_name();
         if schemas.is_empty() {
-            return (saved_type, TokenStream::new());
+            return Ok((saved_type, TokenStream::new()));
+        }
+        if let Some(type_def) =
+            self.expand_tagged_one_of(&saved_type, keyword, schemas, discriminator)?
+        {
+            return Ok((saved_type, type_def));
         }
-        let (variant_names, variant_types): (Vec<_>, Vec<_>) = schemas
+        let variants = schemas.iter().enumerate().collect::<Vec<_>>();
+        let type_def = self.expand_untagged(&saved_type, keyword, &variants)?;
+        Ok((saved_type, type_def))
+    }
+
+    /// Generates an untagged enum for an `anyOf`. The variants are tried from
+    /// the most specific schema to the least specific one, see [`specificity`],
+    /// and `null` schemas make the type optional.
+    fn expand_any_of(&mut self, schemas: &[Schema]) -> Result<FieldType, Error> {
+        let mut variants = Vec::with_capacity(schemas.len());
+        let mut nullable = false;
+        for (i, schema) in schemas.iter().enumerate() {
+            let resolved = self.schema(schema)?;
+            if resolved.type_ == [SimpleTypes::Null] {
+                nullable = true;
+            } else {
+                variants.push((specificity(&resolved), i, schema));
+            }
+        }
+        // A stable sort keeps the order of the schema for equally specific ones
+        variants.sort_by_key(|&(specificity, _, _)| std::cmp::Reverse(specificity));
+        let variants = variants
+            .into_iter()
+            .map(|(_, i, schema)| (i, schema))
+            .collect::<Vec<_>>();
+
+        let typ = match variants[..] {
+            [] => return Ok("serde_json::Value".into()),
+            [(i, schema)] => {
+                self.descend(&["anyOf", &i.to_string()], |this| this.expand_type_(schema))?
+                    .typ
+            }
+            _ => {
+                let type_name = self.union_type_name();
+                let type_def = self.expand_untagged(&type_name, "anyOf", &variants)?;
+                self.types.push((type_name.clone(), type_def));
+                type_name
+            }
+        };
+        Ok(if nullable {
+            FieldType {
+                typ: format!("Option<{}>", typ),
+                attributes: vec![],
+                default: true,
+            }
+        } else {
+            typ.into()
+        })
+    }
+
+    /// Generates an untagged enum with a variant for each of the types which
+    /// `typ` lists, wrapped in an `Option` if one of them is `null`.
+    fn expand_multi_type(&mut self, typ: &Schema) -> Result<FieldType, Error> {
+        let nullable = typ.type_.contains(&SimpleTypes::Null);
+        let mut types = typ
+            .type_
+            .iter()
+            .filter(|&t| *t != SimpleTypes::Null)
+            .cloned()
+            .collect::<Vec<_>>();
+        // Untagged variants are tried in order, `1` must not become a number
+        types.sort_by_key(type_specificity);
+        types.dedup();
+        let single = |t: &SimpleTypes| Schema {
+            type_: vec![t.clone()],
+            ..typ.clone()
+        };
+
+        let inner = match types[..] {
+            [] => "serde_json::Value".to_owned(),
+            [ref t] => self.expand_type_(&single(t))?.typ,
+            _ => {
+                let type_name = self.union_type_name();
+                let saved_type = self.current_type.clone();
+                let saved_field = self.current_field.clone();
+                let mut variants = Vec::with_capacity(types.len());
+                for t in &types {
+                    let variant_name = format!("{:?}", t);
+                    // Objects and arrays defined inline are named after the variant
+                    self.current_field = format!("{}_{}", saved_field, variant_name);
+                    let variant_type = self.expand_type_(&single(t))?.typ;
+                    self.current_type.clone_from(&saved_type);
+                    variants.push((
+                        format_ident!("{}", variant_name),
+                        variant_type.parse::<TokenStream>().unwrap(),
+                    ));
+                }
+                self.current_field = saved_field;
+                let (variant_names, variant_types): (Vec<_>, Vec<_>) = variants.into_iter().unzip();
+                let type_name_ident = syn::Ident::new(&type_name, Span::call_site());
+                let validate = self.validate_variants(
+                    &type_name_ident,
+                    &variant_names
+                        .iter()
+                        .map(|name| (name.clone(), true))
+                        .collect::<Vec<_>>(),
+                );
+                self.types.push((
+                    type_name.clone(),
+                    quote! {
+                        #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+                        #[serde(untagged)]
+                        pub enum #type_name_ident {
+                            #(#variant_names(#variant_types)),*
+                        }
+                        #validate
+                    },
+                ));
+                type_name
+            }
+        };
+        Ok(if nullable {
+            FieldType {
+                typ: format!("Option<{}>", inner),
+                attributes: vec![],
+                default: true,
+            }
+        } else {
+            inner.into()
+        })
+    }
+
+    /// The Rust type which the schema at `location` is overridden with, if any.
+    fn type_override_at(&self, location: &resolver::Location) -> Option<&str> {
+        match location {
+            (None, tokens) => self
+                .type_overrides
+                .get(&pointer::pointer(tokens))
+                .map(|typ| &typ[..]),
+            (Some(_), _) => None,
+        }
+    }
+
+    /// The Rust type which the schema being expanded is overridden with, if
+    /// any.
+    fn type_override(&self) -> Option<String> {
+        self.type_override_at(&self.location.borrow())
+            .map(str::to_owned)
+    }
+
+    /// The path to `schemafy_core` in the generated code.
+    fn schemafy_path(&self) -> TokenStream {
+        self.schemafy_path.parse().unwrap()
+    }
+
+    /// Generates a zero-sized type named `name` for a schema which only allows
+    /// `value`.
+    fn expand_constant(&self, name: &syn::Ident, value: &Value) -> TokenStream {
+        let core = self.schemafy_path();
+        let json = value.to_string();
+        let validate = self.validate_impl(name, TokenStream::new());
+        quote! {
+            #validate
+            #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+            pub struct #name;
+            impl #core constant::Constant for #name {
+                const JSON: &'static str = #json;
+            }
+            impl #core serde::Serialize for #name {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: #core serde::Serializer,
+                {
+                    #core constant::serialize::<Self, S>(serializer)
+                }
+            }
+            impl<'de> #core serde::Deserialize<'de> for #name {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: #core serde::Deserializer<'de>,
+                {
+                    #core constant::deserialize(deserializer)
+                }
+            }
+        }
+    }
+
+    /// Generates an untagged enum for an `enum` which mixes values of different
+    /// types. Each variant holds the zero-sized type of one of the values, see
+    /// [`expand_constant`](Expander::expand_constant), named after the enum
+    /// and the variant, such as `SizeV1` for the value `1` of `Size`.
+    fn expand_mixed_enum(
+        &self,
+        name: &syn::Ident,
+        serde_rename: &Option<TokenStream>,
+        values: &[&Value],
+        optional: bool,
+    ) -> TokenStream {
+        let enum_name = if optional {
+            format_ident!("{}_", name)
+        } else {
+            name.clone()
+        };
+        let mut variant_names = HashSet::new();
+        let (variants, constants): (Vec<_>, Vec<_>) = values
             .iter()
             .enumerate()
-            .map(|(i, schema)| {
-                let name = schema.id.clone().unwrap_or_else(|| format!("Variant{}", i));
+            .map(|(i, value)| {
+                let mut variant_name = value_variant_name(value);
+                if !variant_names.insert(variant_name.clone()) {
+                    variant_name = format!("{}_{}", variant_name, i);
+                }
+                let constant_name = format_ident!("{}{}", name, variant_name);
+                let variant_name = format_ident!("{}", variant_name);
+                (
+                    quote!(#variant_name(#constant_name)),
+                    self.expand_constant(&constant_name, value),
+                )
+            })
+            .unzip();
+        let option = if optional {
+            Some(quote! {
+                pub type #name = Option<#enum_name>;
+            })
+        } else {
+            None
+        };
+        let validate = self.validate_impl(&enum_name, TokenStream::new());
+        quote! {
+            #option
+            #validate
+            #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+            #serde_rename
+            #[serde(untagged)]
+            pub enum #enum_name {
+                #(#variants),*
+            }
+            #(#constants)*
+        }
+    }
+
+    /// Generates an untagged enum named `type_name` with a variant for each of
+    /// the `variants` of the `keyword` union, by their index in the union.
+    fn expand_untagged(
+        &mut self,
+        type_name: &str,
+        keyword: &str,
+        variants: &[(usize, &Schema)],
+    ) -> Result<TokenStream, Error> {
+        let mut used = HashSet::new();
+        let (variant_names, variant_types): (Vec<_>, Vec<_>) = variants
+            .iter()
+            .map(|&(i, schema)| {
+                let name = variant_name(schema, i, &used);
+                used.insert(name.clone());
                 if let Some(ref_) = &schema.ref_ {
-                    let type_ = self.type_ref(ref_);
-                    (format_ident!("{}", &name), format_ident!("{}", &type_))
+                    let type_ = self.referenced_variant_type(ref_, schema)?;
+                    Ok((format_ident!("{}", &name), type_))
                 } else {
-                    let type_name = format!("{}{}", saved_type, &name);
-                    let field_type = self.expand_schema(&type_name, schema);
-                    self.types.push((type_name.clone(), field_type));
-                    (format_ident!("{}", &name), format_ident!("{}", &type_name))
+                    let variant_type = format!("{}{}", type_name, &name);
+                    let field_type = self.descend(&[keyword, &i.to_string()], |this| {
+                        this.expand_schema(&variant_type, schema)
+                    })?;
+                    self.types.push((variant_type.clone(), field_type));
+                    let type_ident = format_ident!("{}", &variant_type);
+                    Ok((format_ident!("{}", &name), quote!(#type_ident)))
                 }
             })
+            .collect::<Result<Vec<_>, Error>>()?
+            .into_iter()
             .unzip();
-        let type_name_ident = syn::Ident::new(&saved_type, Span::call_site());
-        let type_def = quote! {
+        let type_name_ident = syn::Ident::new(type_name, Span::call_site());
+        let validate = self.validate_variants(
+            &type_name_ident,
+            &variant_names
+                .iter()
+                .map(|name| (name.clone(), true))
+                .collect::<Vec<_>>(),
+        );
+        Ok(quote! {
             #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
             #[serde(untagged)]
             pub enum #type_name_ident {
                 #(#variant_names(#variant_types)),*
             }
+            #validate
+        })
+    }
+
+    /// The type of the variant of an enum holding the type referenced by the
+    /// variant schema `schema`. Referenced types may well contain the enum, so
+    /// they are boxed unless they are simple values.
+    fn referenced_variant_type(
+        &mut self,
+        ref_: &str,
+        schema: &Schema,
+    ) -> Result<TokenStream, Error> {
+        let container = type_ident_name(&self.current_type);
+        let type_ = self.referenced_type(ref_)?;
+        let type_ident = format_ident!("{}", &type_);
+        let target = self.schema(schema)?;
+        Ok(if type_ == container || !is_simple(&target) {
+            quote!(Box<#type_ident>)
+        } else {
+            quote!(#type_ident)
+        })
+    }
+
+    /// Generates an internally tagged enum for a `oneOf` (or `anyOf`, under
+    /// `keyword`) whose schemas are told apart by a property: the one named by
+    /// an OpenAPI `discriminator`, or else the one found by
+    /// [`one_of_tag`](Expander::one_of_tag). Returns `None` if there is no such
+    /// property.
+    ///
+    /// The variants of referenced schemas hold the referenced type, tag
+    /// included, the variants of the other schemas their other properties. The
+    /// enum is (de)serialized through [`schemafy_core::tagged`].
+    fn expand_tagged_one_of(
+        &mut self,
+        type_name: &str,
+        keyword: &str,
+        schemas: &[Schema],
+        discriminator: Option<&Discriminator>,
+    ) -> Result<Option<TokenStream>, Error> {
+        let variants = schemas
+            .iter()
+            .enumerate()
+            .map(|(i, schema)| {
+                let location = match schema.ref_ {
+                    Some(ref ref_) => self.ref_location(ref_)?,
+                    None => {
+                        let (document, mut tokens) = self.location.borrow().clone();
+                        tokens.extend(vec![keyword.to_owned(), i.to_string()]);
+                        (document, tokens)
+                    }
+                };
+                Ok((location, self.schema(schema)?))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let (tag, values) = match discriminator {
+            Some(discriminator) => (
+                discriminator.property_name.clone(),
+                self.discriminator_values(discriminator, schemas, &variants)?,
+            ),
+            None => match self.one_of_tag(&variants)? {
+                Some((tag, values)) => (tag, values.into_iter().map(|v| vec![v]).collect()),
+                None => return Ok(None),
+            },
         };
-        (saved_type, type_def)
+
+        let core = self.schemafy_path();
+        let type_name_ident = syn::Ident::new(type_name, Span::call_site());
+        let mut variant_defs = Vec::new();
+        let mut variant_values = Vec::new();
+        let mut serialize_arms = Vec::new();
+        let mut deserialize_arms = Vec::new();
+        for ((variant, (location, schema)), values) in schemas.iter().zip(variants).zip(&values) {
+            let value = &values[0];
+            let variant_name = str_to_ident(&value.to_pascal_case());
+            if let Some(ref ref_) = variant.ref_ {
+                let variant_type = self.referenced_variant_type(ref_, variant)?;
+                variant_values.push((variant_name.clone(), true));
+                variant_defs.push(quote!(#variant_name(#variant_type)));
+                serialize_arms.push(quote! {
+                    #type_name_ident::#variant_name(ref value) => #core serde::Serialize::serialize(value, serializer)
+                });
+                deserialize_arms.push(quote! {
+                    #(#values)|* => tagged.with_tag().map(#type_name_ident::#variant_name)
+                });
+                continue;
+            }
+            // The tag is implied by the variant, which holds the other properties
+            let mut schema = schema.into_owned();
+            schema.properties.remove(&tag);
+            if let Some(ref mut required) = schema.required {
+                required.retain(|property| *property != tag);
+            }
+            variant_values.push((variant_name.clone(), !schema.properties.is_empty()));
+            if schema.properties.is_empty() {
+                variant_defs.push(quote!(#variant_name));
+                serialize_arms.push(quote! {
+                    #type_name_ident::#variant_name => #core tagged::serialize_tag(#tag, #value, serializer)
+                });
+                deserialize_arms.push(quote! {
+                    #(#values)|* => Ok(#type_name_ident::#variant_name)
+                });
+                continue;
+            }
+            let variant_type = format!("{}_{}", type_name, value);
+            let tokens = self.at(location, |this| this.expand_schema(&variant_type, &schema))?;
+            self.types.push((variant_type.clone(), tokens));
+            let variant_type = syn::Ident::new(
+                &replace_invalid_identifier_chars(&variant_type.to_pascal_case()),
+                Span::call_site(),
+            );
+            variant_defs.push(quote!(#variant_name(#variant_type)));
+            serialize_arms.push(quote! {
+                #type_name_ident::#variant_name(ref value) => #core tagged::serialize_with_tag(value, #tag, #value, serializer)
+            });
+            deserialize_arms.push(quote! {
+                #(#values)|* => tagged.without_tag().map(#type_name_ident::#variant_name)
+            });
+        }
+
+        let all_values = values.iter().flatten();
+        let validate = self.validate_variants(&type_name_ident, &variant_values);
+        Ok(Some(quote! {
+            #[derive(Clone, PartialEq, Debug)]
+            pub enum #type_name_ident {
+                #(#variant_defs),*
+            }
+            impl #core serde::Serialize for #type_name_ident {
+                fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: #core serde::Serializer,
+                {
+                    match *self {
+                        #(#serialize_arms,)*
+                    }
+                }
+            }
+            impl<'de> #core serde::Deserialize<'de> for #type_name_ident {
+                fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: #core serde::Deserializer<'de>,
+                {
+                    let tagged = #core tagged::Tagged::deserialize(deserializer, #tag)?;
+                    match tagged.value() {
+                        #(#deserialize_arms,)*
+                        _ => Err(tagged.unknown_variant(&[#(#all_values),*])),
+                    }
+                }
+            }
+            #validate
+        }))
     }
 
-    fn expand_definitions(&mut self, schema: &Schema) {
-        for (name, def) in &schema.definitions {
-            let type_decl = self.expand_schema(name, def);
-            let definition_tokens = match def.description {
-                Some(ref comment) => {
-                    let t = make_doc_comment(comment, LINE_LENGTH);
-                    quote! {
-                        #t
-                        #type_decl
+    /// Finds a property which all of the `oneOf` schemas `variants` require and
+    /// restrict to a single string, through `const` or an `enum` with one value,
+    /// and which is different for each of them.
+    ///
+    /// Returns the name of the property and its value in each of the schemas.
+    fn one_of_tag(
+        &self,
+        variants: &[(resolver::Location, Cow<'_, Schema>)],
+    ) -> Result<Option<(String, Vec<String>)>, Error> {
+        let first = &variants[0].1;
+        let candidates = first.required.iter().flatten();
+        'candidates: for tag in candidates {
+            let mut values = Vec::with_capacity(variants.len());
+            let mut variant_names = HashSet::new();
+            for (location, schema) in variants {
+                let required = schema.required.iter().flatten().any(|r| r == tag);
+                let value = match self.tag_value(location, schema, tag)? {
+                    Some(value) if required => value,
+                    _ => continue 'candidates,
+                };
+                if !variant_names.insert(str_to_ident(&value.to_pascal_case())) {
+                    continue 'candidates;
+                }
+                values.push(value);
+            }
+            return Ok(Some((tag.clone(), values)));
+        }
+        Ok(None)
+    }
+
+    /// Returns the values of the `discriminator` property which select each of
+    /// the `oneOf` schemas `variants`, the first of which names the variant.
+    ///
+    /// Following OpenAPI, a referenced schema is selected by the keys which
+    /// `mapping` maps to it, or else by the name of the schema. Other schemas
+    /// must restrict the property to a single string.
+    fn discriminator_values(
+        &self,
+        discriminator: &Discriminator,
+        schemas: &[Schema],
+        variants: &[(resolver::Location, Cow<'_, Schema>)],
+    ) -> Result<Vec<Vec<String>>, Error> {
+        let tag = &discriminator.property_name;
+        let mut variant_names = HashSet::new();
+        schemas
+            .iter()
+            .zip(variants)
+            .map(|(schema, (location, variant))| {
+                let values = match schema.ref_ {
+                    Some(ref ref_) => {
+                        let schema_name = ref_.rsplit(['/', '#']).next();
+                        let mut values = Vec::new();
+                        for (value, target) in &discriminator.mapping {
+                            // Targets are either schema names or references
+                            let selected = if target.contains(['/', '#']) {
+                                target == ref_
+                                    || self.ref_location(target).ok().as_ref() == Some(location)
+                            } else {
+                                Some(&target[..]) == schema_name
+                            };
+                            if selected {
+                                values.push(value.clone());
+                            }
+                        }
+                        if values.is_empty() {
+                            values.extend(schema_name.map(str::to_owned));
+                        }
+                        values
                     }
+                    None => self
+                        .tag_value(location, variant, tag)?
+                        .into_iter()
+                        .collect(),
+                };
+                match values.first() {
+                    Some(value) if variant_names.insert(str_to_ident(&value.to_pascal_case())) => {
+                        Ok(values)
+                    }
+                    Some(value) => Err(self.located(Error::new(format!(
+                        "Discriminator value `{}` selects more than one schema",
+                        value
+                    )))),
+                    None => Err(self.located(Error::new(format!(
+                        "Unable to determine the value of discriminator `{}` for a schema",
+                        tag
+                    )))),
                 }
-                None => type_decl,
+            })
+            .collect()
+    }
+
+    /// Returns the single string which `schema`, at `location`, allows for its
+    /// property `tag`.
+    fn tag_value(
+        &self,
+        location: &resolver::Location,
+        schema: &Schema,
+        tag: &str,
+    ) -> Result<Option<String>, Error> {
+        let property = match schema.properties.get(tag) {
+            Some(property) => property,
+            None => return Ok(None),
+        };
+        // A referenced property schema is relative to the variant
+        let saved_location = self.location.replace(location.clone());
+        let property = self.schema(property);
+        self.location.replace(saved_location);
+        let property = property?;
+        Ok(match (&property.const_, &property.enum_) {
+            (Some(Value::String(value)), _) => Some(value.clone()),
+            (None, Some(values)) => match &values[..] {
+                [Value::String(value)] => Some(value.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+    }
+
+    fn expand_definitions(&mut self, schema: &Schema) -> Result<(), Error> {
+        let definitions = schema.definitions.iter().map(|def| ("definitions", def));
+        let defs = schema.defs.iter().map(|def| ("$defs", def));
+        for (keyword, (name, def)) in definitions.chain(defs) {
+            let location = self.descend(&[keyword, name], |this| this.location.borrow().clone());
+            if self.generated.contains(&location) {
+                continue;
+            }
+            let type_name = self.claim_type_name(name, location.clone())?;
+            let type_name = if type_name == type_ident_name(name) {
+                name.clone()
+            } else {
+                type_name
             };
-            self.types.push((name.to_string(), definition_tokens));
+            let definition_tokens = self.descend(&[keyword, name], |this| {
+                this.expand_definition(&type_name, def)
+            })?;
+            self.generated.insert(location);
+            self.types.push((type_name, definition_tokens));
         }
+        Ok(())
     }
 
-    fn expand_schema(&mut self, original_name: &str, schema: &Schema) -> TokenStream {
-        self.expand_definitions(schema);
+    fn expand_definition(&mut self, name: &str, def: &Schema) -> Result<TokenStream, Error> {
+        let type_decl = self.expand_schema(name, def)?;
+        Ok(match def.description {
+            Some(ref comment) => {
+                let t = make_doc_comment(comment, LINE_LENGTH);
+                quote! {
+                    #t
+                    #type_decl
+                }
+            }
+            None => type_decl,
+        })
+    }
+
+    /// Generates the referenced types which are not part of a definition of the
+    /// root schema, each of them once.
+    fn expand_pending(&mut self) -> Result<(), Error> {
+        while let Some((name, document, fragment)) = self.pending.pop() {
+            let location = (document, pointer::tokens(&fragment).unwrap_or_default());
+            // The name is only given to the schema at this location, so a
+            // type of that name is the one generated for it inline
+            let type_name = type_ident_name(&name);
+            if !self.generated.insert(location.clone())
+                || self
+                    .types
+                    .iter()
+                    .any(|(n, _)| type_ident_name(n) == type_name)
+            {
+                continue;
+            }
+            let schema = self.subschema(location.0.clone(), &fragment, &name)?;
+            let definition_tokens =
+                self.at(location, |this| this.expand_definition(&name, &schema))?;
+            self.types.push((name, definition_tokens));
+        }
+        Ok(())
+    }
+
+    fn expand_schema(
+        &mut self,
+        original_name: &str,
+        schema: &Schema,
+    ) -> Result<TokenStream, Error> {
+        self.expand_definitions(schema)?;
 
         let pascal_case_name = replace_invalid_identifier_chars(&original_name.to_pascal_case());
         self.current_type.clone_from(&pascal_case_name);
-        let (fields, default) = {
+        if let Some(typ) = self.type_override() {
+            let name = syn::Ident::new(&pascal_case_name, Span::call_site());
+            let typ = typ.parse::<TokenStream>().unwrap();
+            return Ok(quote! {
+                pub type #name = #typ;
+            });
+        }
+        let (fields, default, validations, present, fns, default_fields) = {
             let mut field_expander = FieldExpander {
                 default: true,
+                validations: Vec::new(),
+                present: Vec::new(),
+                fns: Vec::new(),
+                default_fields: Vec::new(),
                 expander: self,
             };
-            let fields = field_expander.expand_fields(original_name, schema);
-            (fields, field_expander.default)
+            let fields = field_expander.expand_fields(original_name, schema)?;
+            (
+                fields,
+                field_expander.default,
+                field_expander.validations,
+                field_expander.present,
+                field_expander.fns,
+                field_expander.default_fields,
+            )
         };
         let name = syn::Ident::new(&pascal_case_name, Span::call_site());
-        let is_struct =
-            !fields.is_empty() || schema.additional_properties == Some(Value::Bool(false));
+        let is_struct = !fields.is_empty() || additional_properties(schema) == Some(Err(false));
         let serde_rename = if name == original_name {
             None
         } else {
@@ -606,16 +2267,50 @@ impl<'r> Expander<'r> {
                 #[serde(rename = #original_name)]
             })
         };
-        let is_enum = schema.enum_.as_ref().map_or(false, |e| !e.is_empty());
+        if let Some(value) = constant(schema).filter(|_| !is_struct) {
+            return Ok(self.expand_constant(&name, value));
+        }
+        let enum_values = schema.enum_.as_ref();
+        let is_enum = enum_values.map_or(false, |e| !e.is_empty());
         let type_decl = if is_struct {
-            let serde_deny_unknown = if schema.additional_properties == Some(Value::Bool(false))
+            let serde_deny_unknown = if additional_properties(schema) == Some(Err(false))
                 && schema.pattern_properties.is_empty()
             {
                 Some(quote! { #[serde(deny_unknown_fields)] })
             } else {
                 None
             };
-            if default {
+            let validate = self.validate_struct(&name, schema, &validations, &present);
+            if !fns.is_empty() {
+                // The `default` values of the fields are used by serde and by
+                // the `Default` impl, as far as every field has a default
+                let default_impl = if default {
+                    Some(quote! {
+                        impl Default for #name {
+                            fn default() -> Self {
+                                #name {
+                                    #(#default_fields),*
+                                }
+                            }
+                        }
+                    })
+                } else {
+                    None
+                };
+                quote! {
+                    #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+                    #serde_rename
+                    #serde_deny_unknown
+                    pub struct #name {
+                        #(#fields),*
+                    }
+                    impl #name {
+                        #(#fns)*
+                    }
+                    #default_impl
+                    #validate
+                }
+            } else if default {
                 quote! {
                     #[derive(Clone, PartialEq, Debug, Default, Deserialize, Serialize)]
                     #serde_rename
@@ -623,6 +2318,7 @@ impl<'r> Expander<'r> {
                     pub struct #name {
                         #(#fields),*
                     }
+                    #validate
                 }
             } else {
                 quote! {
@@ -632,26 +2328,27 @@ impl<'r> Expander<'r> {
                     pub struct #name {
                         #(#fields),*
                     }
+                    #validate
                 }
             }
         } else if is_enum {
             let mut optional = false;
             let mut repr_i64 = false;
             let variants = if schema.enum_names.as_ref().map_or(false, |e| !e.is_empty()) {
-                let values = schema.enum_.as_ref().map_or(&[][..], |v| v);
+                let values = enum_values.map_or(&[][..], |v| v);
                 let names = schema.enum_names.as_ref().map_or(&[][..], |v| v);
                 if names.len() != values.len() {
-                    panic!(
+                    return Err(self.located(Error::new(format!(
                         "enumNames(length {}) and enum(length {}) have different length",
                         names.len(),
                         values.len()
-                    )
+                    ))));
                 }
                 names
                     .iter()
                     .enumerate()
                     .map(|(idx, name)| (&values[idx], name))
-                    .flat_map(|(value, name)| {
+                    .filter_map(|(value, name)| {
                         let pascal_case_variant = name.to_pascal_case();
                         let variant_name =
                             rename_keyword("", &pascal_case_variant).unwrap_or_else(|| {
@@ -659,33 +2356,37 @@ impl<'r> Expander<'r> {
                                 quote!(#v)
                             });
                         match value {
-                            Value::String(ref s) => Some(quote! {
+                            Value::String(ref s) => Some(Ok(quote! {
                                 #[serde(rename = #s)]
                                 #variant_name
-                            }),
+                            })),
                             Value::Number(ref n) => {
                                 repr_i64 = true;
                                 let num = syn::LitInt::new(&n.to_string(), Span::call_site());
-                                Some(quote! {
+                                Some(Ok(quote! {
                                     #variant_name = #num
-                                })
+                                }))
                             }
                             Value::Null => {
                                 optional = true;
                                 None
                             }
-                            _ => panic!("Expected string,bool or number for enum got `{}`", value),
+                            _ => Some(Err(self.located(Error::new(format!(
+                                "Expected string,bool or number for enum got `{}`",
+                                value
+                            ))))),
                         }
                     })
-                    .collect::<Vec<_>>()
+                    .collect::<Result<Vec<_>, Error>>()?
             } else {
-                schema
-                    .enum_
-                    .as_ref()
-                    .map_or(&[][..], |v| v)
-                    .iter()
-                    .flat_map(|v| match *v {
-                        Value::String(ref v) => {
+                let values = enum_values.map_or(&[][..], |v| v);
+                optional = values.iter().any(Value::is_null);
+                let values = values.iter().filter(|v| !v.is_null()).collect::<Vec<_>>();
+                if values.iter().all(|v| v.is_string()) {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(|v| {
                             let pascal_case_variant = v.to_pascal_case();
                             let variant_name = rename_keyword("", &pascal_case_variant)
                                 .unwrap_or_else(|| {
@@ -693,25 +2394,50 @@ impl<'r> Expander<'r> {
                                         syn::Ident::new(&pascal_case_variant, Span::call_site());
                                     quote!(#v)
                                 });
-                            Some(if pascal_case_variant == *v {
+                            if pascal_case_variant == *v {
                                 variant_name
                             } else {
                                 quote! {
                                     #[serde(rename = #v)]
                                     #variant_name
                                 }
-                            })
-                        }
-                        Value::Null => {
-                            optional = true;
-                            None
-                        }
-                        _ => panic!("Expected string for enum got `{}`", v),
-                    })
-                    .collect::<Vec<_>>()
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                } else if values.iter().all(|v| v.is_i64()) {
+                    repr_i64 = true;
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_i64())
+                        .map(|n| {
+                            let variant_name = format_ident!("{}", value_variant_name(&n.into()));
+                            let num = proc_macro2::Literal::i64_unsuffixed(n);
+                            quote! {
+                                #variant_name = #num
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                } else if values.iter().all(|v| v.is_boolean()) {
+                    // Only `bool` can hold `true` and `false`
+                    let typ = if optional {
+                        quote!(Option<bool>)
+                    } else {
+                        quote!(bool)
+                    };
+                    return Ok(quote! {
+                        pub type #name = #typ;
+                    });
+                } else {
+                    return Ok(self.expand_mixed_enum(&name, &serde_rename, &values, optional));
+                }
+            };
+            let enum_name = if optional {
+                format_ident!("{}_", name)
+            } else {
+                name.clone()
             };
-            if optional {
-                let enum_name = syn::Ident::new(&format!("{}_", name), Span::call_site());
+            let validate = self.validate_impl(&enum_name, TokenStream::new());
+            let enum_decl = if optional {
                 if repr_i64 {
                     quote! {
                         pub type #name = Option<#enum_name>;
@@ -749,38 +2475,69 @@ impl<'r> Expander<'r> {
                         #(#variants),*
                     }
                 }
+            };
+            quote! {
+                #enum_decl
+                #validate
             }
+        } else if let Some(checks) = self.newtype_checks(schema) {
+            self.expand_newtype(&name, schema, checks)?
         } else {
             let typ = self
-                .expand_type("", true, schema)
+                .expand_type("", true, schema)?
                 .typ
                 .parse::<TokenStream>()
                 .unwrap();
             // Skip self-referential types, e.g. `struct Schema = Schema`
             if name == typ.to_string() {
-                return TokenStream::new();
+                return Ok(TokenStream::new());
             }
-            return quote! {
+            return Ok(quote! {
                 pub type #name = #typ;
-            };
+            });
         };
-        type_decl
+        Ok(type_decl)
     }
 
+    /// Generates the types of `schema`, panicking if that fails.
+    ///
+    /// See [`try_expand`](Expander::try_expand).
     pub fn expand(&mut self, schema: &Schema) -> TokenStream {
+        self.try_expand(schema)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Generates the types of `schema`, or returns the first error found in
+    /// the schema.
+    pub fn try_expand(&mut self, schema: &Schema) -> Result<TokenStream, Error> {
+        // The types of the root schema keep their names, even if a schema in
+        // another document of the same name is referenced first
+        if let Some(name) = self.root_name {
+            self.type_names
+                .insert(type_ident_name(name), (None, Vec::new()));
+            self.generated.insert((None, Vec::new()));
+        }
+        let definitions = schema.definitions.keys().map(|name| ("definitions", name));
+        let defs = schema.defs.keys().map(|name| ("$defs", name));
+        for (keyword, name) in definitions.chain(defs) {
+            self.type_names
+                .entry(type_ident_name(name))
+                .or_insert_with(|| (None, vec![keyword.to_owned(), name.clone()]));
+        }
         match self.root_name {
             Some(name) => {
-                let schema = self.expand_schema(name, schema);
+                let schema = self.expand_schema(name, schema)?;
                 self.types.push((name.to_string(), schema));
             }
-            None => self.expand_definitions(schema),
+            None => self.expand_definitions(schema)?,
         }
+        self.expand_pending()?;
 
         let types = self.types.iter().map(|t| &t.1);
 
-        quote! {
+        Ok(quote! {
             #( #types )*
-        }
+        })
     }
 
     pub fn expand_root(&mut self) -> TokenStream {
@@ -802,9 +2559,10 @@ mod tests {
         assert_eq!(expander.type_ref("#"), "SchemaName");
         assert_eq!(expander.type_ref(""), "SchemaName");
         assert_eq!(expander.type_ref("1"), "_1");
+        // Another document is named after its file rather than the root schema
         assert_eq!(
             expander.type_ref("http://example.com/schema.json#"),
-            "SchemaName"
+            "Schema"
         );
         assert_eq!(
             expander.type_ref("http://example.com/normalField#withFragment"),
@@ -0,0 +1,468 @@
+//! Resolution of JSON pointers ([RFC 6901](https://tools.ietf.org/html/rfc6901))
+//! in the fragment of a `$ref`.
+
+use std::borrow::Cow;
+
+use crate::{bool_schema, resolver::percent_decode, Schema, SchemaDependencies};
+
+/// Splits a (percent-encoded) URI fragment into the reference tokens of the
+/// JSON pointer it holds, with `~1` and `~0` unescaped.
+///
+/// Returns `None` if the fragment is not a JSON pointer.
+pub(crate) fn tokens(fragment: &str) -> Option<Vec<String>> {
+    let pointer = percent_decode(fragment);
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    let pointer = pointer.strip_prefix('/')?;
+    Some(
+        pointer
+            .split('/')
+            .map(|token| token.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+/// Joins reference tokens into a JSON pointer.
+pub(crate) fn pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|token| format!("/{}", token.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+/// Joins reference tokens into a (percent-encoded) URI fragment.
+pub(crate) fn fragment(tokens: &[String]) -> String {
+    pointer(tokens).replace('%', "%25")
+}
+
+/// Whether `tokens` point at the document itself or at one of its (nested)
+/// definitions, which are generated without being referenced.
+pub(crate) fn is_definition(tokens: &[String]) -> bool {
+    tokens
+        .chunks(2)
+        .all(|pair| pair.len() == 2 && (pair[0] == "definitions" || pair[0] == "$defs"))
+}
+
+/// Looks up the subschema of `schema` which `tokens` point at.
+pub(crate) fn resolve<'s>(schema: &'s Schema, tokens: &[String]) -> Option<Cow<'s, Schema>> {
+    resolve_with(schema, tokens, |_| ())
+}
+
+/// Like [`resolve`], but calls `visit` with every schema on the way to the
+/// subschema, starting with `schema` itself.
+pub(crate) fn resolve_with<'s>(
+    schema: &'s Schema,
+    tokens: &[String],
+    mut visit: impl FnMut(&Schema),
+) -> Option<Cow<'s, Schema>> {
+    let mut schema = schema;
+    let mut tokens = tokens.iter().map(String::as_str).peekable();
+    while let Some(token) = tokens.next() {
+        visit(schema);
+        schema = match token {
+            "definitions" => schema.definitions.get(tokens.next()?)?,
+            "$defs" => schema.defs.get(tokens.next()?)?,
+            "properties" => schema.properties.get(tokens.next()?)?,
+            "patternProperties" => schema.pattern_properties.get(tokens.next()?)?,
+            "dependentSchemas" => schema.dependent_schemas.get(tokens.next()?)?,
+            "allOf" => index(schema.all_of.as_deref(), tokens.next()?)?,
+            "anyOf" => index(schema.any_of.as_deref(), tokens.next()?)?,
+            "oneOf" => index(schema.one_of.as_deref(), tokens.next()?)?,
+            "prefixItems" => index(schema.prefix_items.as_deref(), tokens.next()?)?,
+            // `items` holds either a single schema or an array of them
+            "items" => match schema.items.as_ref()?.as_schema() {
+                Ok(item) => item,
+                Err(tuple) => index(Some(tuple), tokens.next()?)?,
+            },
+            "not" => schema.not.as_deref()?,
+            "contains" => schema.contains.as_deref()?,
+            "propertyNames" => schema.property_names.as_deref()?,
+            "if" => schema.if_.as_deref()?,
+            "then" => schema.then.as_deref()?,
+            "else" => schema.else_.as_deref()?,
+            "additionalItems"
+            | "additionalProperties"
+            | "unevaluatedItems"
+            | "unevaluatedProperties" => {
+                let subschema = match token {
+                    "additionalItems" => schema.additional_items.as_ref()?.as_schema(),
+                    "additionalProperties" => schema.additional_properties.as_ref()?.as_schema(),
+                    "unevaluatedItems" => schema.unevaluated_items.as_ref()?.as_schema(),
+                    _ => schema.unevaluated_properties.as_ref()?.as_schema(),
+                };
+                match subschema {
+                    Ok(subschema) => subschema,
+                    // `true` and `false` have no subschemas of their own
+                    Err(value) => {
+                        return tokens
+                            .next()
+                            .is_none()
+                            .then(|| Cow::Owned(bool_schema(value)))
+                    }
+                }
+            }
+            "dependencies" => match schema.dependencies.as_ref()?.get(tokens.next()?)? {
+                SchemaDependencies::Variant0(dependency) => dependency,
+                SchemaDependencies::Variant1(_) => return None,
+            },
+            _ => return None,
+        };
+    }
+    Some(Cow::Borrowed(schema))
+}
+
+/// Returns the subschemas directly below `schema`, along with the reference
+/// tokens leading to them.
+///
+/// Subschemas which are kept as plain JSON values, such as
+/// `additionalProperties`, are not included.
+pub(crate) fn children(schema: &Schema) -> Vec<(Vec<String>, &Schema)> {
+    let mut children = Vec::new();
+    let maps = [
+        ("definitions", &schema.definitions),
+        ("$defs", &schema.defs),
+        ("properties", &schema.properties),
+        ("patternProperties", &schema.pattern_properties),
+        ("dependentSchemas", &schema.dependent_schemas),
+    ];
+    for (keyword, schemas) in maps {
+        for (key, child) in schemas {
+            children.push((vec![keyword.to_owned(), key.clone()], child));
+        }
+    }
+    for (key, dependency) in schema.dependencies.iter().flatten() {
+        if let SchemaDependencies::Variant0(child) = dependency {
+            children.push((vec!["dependencies".to_owned(), key.clone()], child));
+        }
+    }
+    let arrays = [
+        ("allOf", schema.all_of.as_deref()),
+        ("anyOf", schema.any_of.as_deref()),
+        ("oneOf", schema.one_of.as_deref()),
+        ("prefixItems", schema.prefix_items.as_deref()),
+        (
+            "items",
+            schema
+                .items
+                .as_ref()
+                .and_then(|items| items.as_schema().err()),
+        ),
+    ];
+    for (keyword, schemas) in arrays {
+        for (i, child) in schemas.into_iter().flatten().enumerate() {
+            children.push((vec![keyword.to_owned(), i.to_string()], child));
+        }
+    }
+    let singles = [
+        (
+            "items",
+            schema
+                .items
+                .as_ref()
+                .and_then(|items| items.as_schema().ok()),
+        ),
+        ("not", schema.not.as_deref()),
+        ("contains", schema.contains.as_deref()),
+        ("propertyNames", schema.property_names.as_deref()),
+        ("if", schema.if_.as_deref()),
+        ("then", schema.then.as_deref()),
+        ("else", schema.else_.as_deref()),
+        (
+            "additionalItems",
+            schema
+                .additional_items
+                .as_ref()
+                .and_then(|s| s.as_schema().ok()),
+        ),
+        (
+            "additionalProperties",
+            schema
+                .additional_properties
+                .as_ref()
+                .and_then(|s| s.as_schema().ok()),
+        ),
+        (
+            "unevaluatedItems",
+            schema
+                .unevaluated_items
+                .as_ref()
+                .and_then(|s| s.as_schema().ok()),
+        ),
+        (
+            "unevaluatedProperties",
+            schema
+                .unevaluated_properties
+                .as_ref()
+                .and_then(|s| s.as_schema().ok()),
+        ),
+    ];
+    for (keyword, child) in singles {
+        if let Some(child) = child {
+            children.push((vec![keyword.to_owned()], child));
+        }
+    }
+    children
+}
+
+fn index<'s>(schemas: Option<&'s [Schema]>, token: &str) -> Option<&'s Schema> {
+    schemas?.get(token.parse::<usize>().ok()?)
+}
+
+/// Derives a name for the subschema which `tokens` point at, from the name of
+/// the closest enclosing definition (or `document_name`) and the keywords on
+/// the way to the subschema.
+///
+/// `#/definitions/a/properties/b/items` is named `a_b_item` for instance.
+pub(crate) fn type_name(document_name: &str, tokens: &[String]) -> String {
+    let mut parts = vec![document_name.to_owned()];
+    let mut tokens = tokens.iter().map(String::as_str).peekable();
+    while let Some(token) = tokens.next() {
+        let index = tokens.peek().and_then(|token| token.parse::<usize>().ok());
+        let part = match token {
+            "definitions" | "$defs" => {
+                parts.clear();
+                tokens.next().map(str::to_owned)
+            }
+            "properties" | "patternProperties" | "dependentSchemas" | "dependencies" => {
+                tokens.next().map(str::to_owned)
+            }
+            "items" | "prefixItems" => match index {
+                Some(i) => {
+                    tokens.next();
+                    Some(format!("item{}", i))
+                }
+                None => Some("item".to_owned()),
+            },
+            "additionalItems" | "unevaluatedItems" | "contains" => Some("item".to_owned()),
+            "additionalProperties" | "unevaluatedProperties" => Some("value".to_owned()),
+            "allOf" | "anyOf" | "oneOf" => {
+                tokens.next();
+                index.map(|i| format!("variant{}", i))
+            }
+            // Unknown keywords are taken to hold named schemas, such as
+            // `#/components/schemas/Pet` in OpenAPI documents
+            token => {
+                parts.clear();
+                Some(token.to_owned())
+            }
+        };
+        parts.extend(part);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts.join("_")
+}
+
+/// Finds the line and column (both starting at 1) of the value which the JSON
+/// pointer `pointer` points at in the JSON text `json`.
+///
+/// Returns `None` if there is no such value.
+pub(crate) fn text_position(json: &str, pointer: &str) -> Option<(usize, usize)> {
+    let mut scanner = Scanner { json, offset: 0 };
+    scanner.skip_whitespace();
+    if !pointer.is_empty() {
+        for token in pointer.strip_prefix('/')?.split('/') {
+            scanner.enter(&token.replace("~1", "/").replace("~0", "~"))?;
+        }
+    }
+    let before = &json[..scanner.offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+    Some((line, column))
+}
+
+/// A cursor over a valid JSON text.
+struct Scanner<'a> {
+    json: &'a str,
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.json.as_bytes().get(self.offset).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        if self.peek()? != byte {
+            return None;
+        }
+        self.offset += 1;
+        Some(())
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.offset += 1;
+        }
+    }
+
+    /// Moves from the start of an object or array to the start of its member
+    /// `token`.
+    fn enter(&mut self, token: &str) -> Option<()> {
+        match self.peek()? {
+            b'{' => {
+                self.offset += 1;
+                loop {
+                    self.skip_whitespace();
+                    let key = serde_json::from_str::<String>(self.string()?).ok()?;
+                    self.skip_whitespace();
+                    self.expect(b':')?;
+                    self.skip_whitespace();
+                    if key == token {
+                        return Some(());
+                    }
+                    self.skip_value()?;
+                    self.skip_whitespace();
+                    self.expect(b',')?;
+                }
+            }
+            b'[' => {
+                let index = token.parse::<usize>().ok()?;
+                self.offset += 1;
+                for _ in 0..index {
+                    self.skip_whitespace();
+                    self.skip_value()?;
+                    self.skip_whitespace();
+                    self.expect(b',')?;
+                }
+                self.skip_whitespace();
+                match self.peek()? {
+                    b']' => None,
+                    _ => Some(()),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Skips a string, returning it with its quotes and escapes.
+    fn string(&mut self) -> Option<&'a str> {
+        let start = self.offset;
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'\\' => self.offset += 2,
+                b'"' => break,
+                _ => self.offset += 1,
+            }
+        }
+        self.offset += 1;
+        Some(&self.json[start..self.offset])
+    }
+
+    /// Skips a value up to the comma or bracket which ends it.
+    fn skip_value(&mut self) -> Option<()> {
+        let mut depth = 0;
+        loop {
+            match self.peek()? {
+                b'"' => {
+                    self.string()?;
+                }
+                b'{' | b'[' => {
+                    depth += 1;
+                    self.offset += 1;
+                }
+                b',' | b'}' | b']' if depth == 0 => return Some(()),
+                b'}' | b']' => {
+                    depth -= 1;
+                    self.offset += 1;
+                }
+                _ => self.offset += 1,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(tokens: &[&str]) -> Vec<String> {
+        tokens.iter().map(|&token| token.to_owned()).collect()
+    }
+
+    #[test]
+    fn unescape_tokens() {
+        assert_eq!(tokens(""), Some(vec![]));
+        assert_eq!(
+            tokens("/properties/a~1b/properties/m~0n/properties/with%20space"),
+            Some(strings(&[
+                "properties",
+                "a/b",
+                "properties",
+                "m~n",
+                "properties",
+                "with space"
+            ]))
+        );
+        assert_eq!(
+            tokens("/properties/~01"),
+            Some(strings(&["properties", "~1"]))
+        );
+        assert_eq!(tokens("anchor"), None);
+    }
+
+    #[test]
+    fn fragment_round_trip() {
+        let pointer = strings(&["properties", "a/b", "m~n", "100%"]);
+        assert_eq!(fragment(&pointer), "/properties/a~1b/m~0n/100%25");
+        assert_eq!(tokens(&fragment(&pointer)), Some(pointer));
+    }
+
+    #[test]
+    fn resolve_pointer() {
+        let schema: Schema = serde_json::from_value(serde_json::json!({
+            "definitions": {
+                "a": {
+                    "properties": {
+                        "b": { "items": { "type": "string" } },
+                        "c": { "items": [{ "type": "integer" }, { "type": "boolean" }] },
+                        "d": { "additionalProperties": { "type": "number" } },
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let resolve =
+            |pointer| resolve(&schema, &tokens(pointer).unwrap()).map(|s| s.type_.clone());
+        use crate::SimpleTypes::*;
+        assert_eq!(
+            resolve("/definitions/a/properties/b/items"),
+            Some(vec![String])
+        );
+        assert_eq!(
+            resolve("/definitions/a/properties/c/items/1"),
+            Some(vec![Boolean])
+        );
+        assert_eq!(
+            resolve("/definitions/a/properties/d/additionalProperties"),
+            Some(vec![Number])
+        );
+        assert_eq!(resolve("/definitions/a/properties/e"), None);
+        assert_eq!(resolve("/definitions/a/properties/c/items"), None);
+    }
+
+    #[test]
+    fn derive_type_name() {
+        let name = |pointer| type_name("root", &tokens(pointer).unwrap());
+        assert_eq!(name("/definitions/a/properties/b/items"), "a_b_item");
+        assert_eq!(name("/definitions/a/definitions/b"), "b");
+        assert_eq!(name("/properties/address"), "root_address");
+        assert_eq!(name("/properties/pair/items/0"), "root_pair_item0");
+        assert_eq!(name("/oneOf/1/properties/x"), "root_variant1_x");
+        assert_eq!(name("/components/schemas/Pet"), "Pet");
+        assert_eq!(name(""), "root");
+    }
+
+    #[test]
+    fn find_text_position() {
+        let json = "{\n  \"a\": [1, {\"b\": \"}\\\"\"}, {\"c\": true}],\n  \"d/e\": {\n    \"f\": null\n  }\n}";
+        assert_eq!(text_position(json, ""), Some((1, 1)));
+        assert_eq!(text_position(json, "/a"), Some((2, 8)));
+        assert_eq!(text_position(json, "/a/2/c"), Some((2, 32)));
+        assert_eq!(text_position(json, "/d~1e/f"), Some((4, 10)));
+        assert_eq!(text_position(json, "/a/3"), None);
+        assert_eq!(text_position(json, "/g"), None);
+    }
+}
@@ -0,0 +1,382 @@
+//! Loading of the schema documents referenced through `$ref`.
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fmt::{self, Write},
+    io,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use uriparse::{Fragment, URIReference, URI};
+
+use crate::{pointer, Dialect, Error, Schema};
+
+/// An absolute URI identifying a schema document.
+pub type Uri = URI<'static>;
+
+/// A source of schema documents.
+///
+/// The [`Expander`](crate::Expander) consults its resolver for every `$ref`
+/// which points outside of the root schema, passing the URI of the
+/// referenced document without its fragment.
+pub trait Resolver {
+    /// Returns the schema document identified by `uri`.
+    ///
+    /// Fails with [`io::ErrorKind::NotFound`] if the resolver does not know
+    /// the document.
+    fn resolve(&self, uri: &Uri) -> io::Result<Value>;
+}
+
+impl fmt::Debug for dyn Resolver + '_ {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Resolver")
+    }
+}
+
+/// Reads `file` URIs from the file system.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FileResolver;
+
+impl Resolver for FileResolver {
+    fn resolve(&self, uri: &Uri) -> io::Result<Value> {
+        let path = uri_path(uri).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("`{}` is not a file URI", uri),
+            )
+        })?;
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+/// Serves schema documents from memory.
+impl Resolver for HashMap<Uri, Value> {
+    fn resolve(&self, uri: &Uri) -> io::Result<Value> {
+        self.get(uri).cloned().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("Unknown schema `{}`", uri))
+        })
+    }
+}
+
+/// Tries the first resolver and falls back to the second one for documents
+/// the first one does not know.
+impl<A, B> Resolver for (A, B)
+where
+    A: Resolver,
+    B: Resolver,
+{
+    fn resolve(&self, uri: &Uri) -> io::Result<Value> {
+        match self.0.resolve(uri) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => self.1.resolve(uri),
+            result => result,
+        }
+    }
+}
+
+impl<R> Resolver for &R
+where
+    R: Resolver + ?Sized,
+{
+    fn resolve(&self, uri: &Uri) -> io::Result<Value> {
+        (**self).resolve(uri)
+    }
+}
+
+/// Returns the `file` URI of an absolute path, which is how the
+/// [`Generator`](crate::Generator) asks a [`Resolver`] for the file.
+pub fn file_uri(path: &Path) -> Uri {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z'
+            | b'a'..=b'z'
+            | b'0'..=b'9'
+            | b'-'
+            | b'.'
+            | b'_'
+            | b'~'
+            | b'/'
+            | b':'
+            | b'@'
+            | b'!'
+            | b'$'
+            | b'&'
+            | b'\''
+            | b'('
+            | b')'
+            | b'*'
+            | b'+'
+            | b','
+            | b';'
+            | b'=' => uri.push(byte as char),
+            _ => write!(uri, "%{:02X}", byte).unwrap(),
+        }
+    }
+    URI::try_from(uri.as_str())
+        .unwrap_or_else(|err| panic!("Invalid file URI `{}`: {}", uri, err))
+        .into_owned()
+}
+
+/// Returns the `file` URI of the current directory, which relative references
+/// are resolved against if the root schema has no URI of its own.
+pub(crate) fn current_dir_uri() -> Uri {
+    let dir = std::env::current_dir().expect("Current directory");
+    file_uri(&dir.join(""))
+}
+
+/// Returns the path of a `file` URI.
+pub(crate) fn uri_path(uri: &Uri) -> Option<PathBuf> {
+    if uri.scheme().as_str() != "file" {
+        return None;
+    }
+    let path = percent_decode(&uri.path().to_string());
+    // `file:///C:/schema.json` names `C:/schema.json` on Windows
+    let path = if cfg!(windows) && path.get(2..3) == Some(":") {
+        &path[1..]
+    } else {
+        &path[..]
+    };
+    Some(PathBuf::from(path))
+}
+
+/// The path of a `file` URI, as that is easier to find, or else the URI.
+pub(crate) fn display_uri(uri: &Uri) -> String {
+    match uri_path(uri) {
+        Some(path) => path.display().to_string(),
+        None => uri.to_string(),
+    }
+}
+
+pub(crate) fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = s
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Splits `reference` into the URI of the document it points into and the
+/// (still percent-encoded) fragment within that document.
+///
+/// Returns `None` for references which are not valid URI references.
+pub(crate) fn resolve_reference(base: &Uri, reference: &str) -> Option<(Uri, String)> {
+    let reference = URIReference::try_from(reference).ok()?;
+    let fragment = reference
+        .fragment()
+        .map_or_else(String::new, Fragment::to_string);
+    let mut document = base.resolve(&reference).into_owned();
+    document.set_fragment(None::<Fragment>).unwrap();
+    Some((document, fragment))
+}
+
+/// Returns the base URI within `schema` if it changes it through `id`, given
+/// the base URI `parent` of the enclosing schema.
+pub(crate) fn scope(parent: &Uri, schema: &Schema) -> Option<Uri> {
+    let id = schema.id_.as_ref().or(schema.id.as_ref())?;
+    resolve_reference(parent, id).map(|(uri, _)| uri)
+}
+
+/// Where a schema lives: its document (`None` for the root schema) and the
+/// reference tokens leading to it within that document.
+pub(crate) type Location = (Option<Uri>, Vec<String>);
+
+/// The schemas which can be referenced by URI instead of by a JSON pointer.
+#[derive(Default)]
+pub(crate) struct Index {
+    /// Documents and subschemas with an `id`, by their URI
+    resources: HashMap<Uri, Location>,
+    /// Subschemas with an anchor, by the URI of their resource and the anchor
+    anchors: HashMap<(Uri, String), Location>,
+}
+
+impl Index {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.resources.is_empty()
+    }
+
+    pub(crate) fn resource(&self, uri: &Uri) -> Option<&Location> {
+        self.resources.get(uri)
+    }
+
+    pub(crate) fn anchor(&self, uri: &Uri, anchor: &str) -> Option<&Location> {
+        self.anchors.get(&(uri.clone(), anchor.to_owned()))
+    }
+
+    /// Adds the document `schema`, retrieved from `uri`, and all of the
+    /// identified schemas within it.
+    pub(crate) fn add_document(&mut self, document: Option<Uri>, uri: Uri, schema: &Schema) {
+        self.resources
+            .insert(uri.clone(), (document.clone(), Vec::new()));
+        self.add(&document, schema, &uri, &mut Vec::new());
+    }
+
+    fn add(
+        &mut self,
+        document: &Option<Uri>,
+        schema: &Schema,
+        scope: &Uri,
+        path: &mut Vec<String>,
+    ) {
+        let location = || (document.clone(), path.clone());
+        let id = schema.id_.as_ref().or(schema.id.as_ref());
+        let scope = match id.and_then(|id| resolve_reference(scope, id)) {
+            Some((uri, fragment)) => {
+                // Up to draft 7 `"id": "#foo"` declares an anchor
+                if !fragment.is_empty() {
+                    self.anchors.insert((uri.clone(), fragment), location());
+                }
+                self.resources.entry(uri.clone()).or_insert_with(location);
+                uri
+            }
+            None => scope.clone(),
+        };
+        if let Some(ref anchor) = schema.anchor {
+            self.anchors
+                .insert((scope.clone(), anchor.clone()), location());
+        }
+        for (tokens, child) in pointer::children(schema) {
+            let len = path.len();
+            path.extend(tokens);
+            self.add(document, child, &scope, path);
+            path.truncate(len);
+        }
+    }
+}
+
+/// Resolves and parses the schema document behind `uri`, which is called
+/// `name` if it cannot be read.
+///
+/// Errors in reading the document are left without a location, the
+/// reference to the document is the best place to report them.
+pub(crate) fn load_document(
+    resolver: &dyn Resolver,
+    uri: &Uri,
+    name: &str,
+) -> Result<Schema, Error> {
+    let mut schema = resolver.resolve(uri).map_err(|err| {
+        // `serde_json::Error`s know where the document stopped being valid JSON
+        match err
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<serde_json::Error>())
+        {
+            Some(json) => {
+                // The position is reported as part of the location
+                let message = json.to_string();
+                let position = format!(" at line {} column {}", json.line(), json.column());
+                let message = message.strip_suffix(&position).unwrap_or(&message);
+                Error::new(format!("Invalid JSON: {}", message))
+                    .at(uri, "")
+                    .with_position(json.line(), json.column())
+            }
+            None => Error::new(format!("Unable to read `{}`: {}", name, err)),
+        }
+    })?;
+    Dialect::detect(&schema).normalize(&mut schema);
+    serde_json::from_value(schema)
+        .map_err(|err| Error::new(format!("Invalid schema: {}", err)).at(uri, ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_uri_round_trip() {
+        let path = Path::new("/schemas/my schemas/100%.json");
+        let uri = file_uri(path);
+        assert_eq!(uri.to_string(), "file:///schemas/my%20schemas/100%25.json");
+        assert_eq!(uri_path(&uri).unwrap(), path);
+    }
+
+    #[test]
+    fn resolve_relative_reference() {
+        let base = file_uri(Path::new("/schemas/types/root.json"));
+        let (document, fragment) =
+            resolve_reference(&base, "../common.json#/definitions/Address").unwrap();
+        assert_eq!(document.to_string(), "file:///schemas/common.json");
+        assert_eq!(fragment, "/definitions/Address");
+
+        let (document, fragment) = resolve_reference(&base, "#/definitions/Name").unwrap();
+        assert_eq!(document, base);
+        assert_eq!(fragment, "/definitions/Name");
+    }
+
+    #[test]
+    fn fall_back_to_second_resolver() {
+        let uri = |s| Uri::try_from(s).unwrap().into_owned();
+        let first = vec![(uri("http://example.com/a.json"), Value::from(1))]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let second = vec![
+            (uri("http://example.com/a.json"), Value::from(2)),
+            (uri("http://example.com/b.json"), Value::from(2)),
+        ]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+        let resolver = (first, second);
+
+        assert_eq!(
+            resolver.resolve(&uri("http://example.com/a.json")).unwrap(),
+            1
+        );
+        assert_eq!(
+            resolver.resolve(&uri("http://example.com/b.json")).unwrap(),
+            2
+        );
+        assert_eq!(
+            resolver
+                .resolve(&uri("http://example.com/c.json"))
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn report_unreadable_documents() {
+        struct Invalid;
+
+        impl Resolver for Invalid {
+            fn resolve(&self, _: &Uri) -> io::Result<Value> {
+                Ok(serde_json::from_str("{\n  \"type\": }")?)
+            }
+        }
+
+        let uri = file_uri(Path::new("/schemas/root.json"));
+        let err = load_document(&Invalid, &uri, "root.json").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "/schemas/root.json:2:11: Invalid JSON: expected value"
+        );
+
+        let missing = vec![].into_iter().collect::<HashMap<_, _>>();
+        let err = load_document(&missing, &uri, "root.json").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unable to read `root.json`: Unknown schema `file:///schemas/root.json`"
+        );
+    }
+}
@@ -1,3 +1,12 @@
+#[doc = " The OpenAPI discriminator of a oneOf or anyOf"]
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(rename = "discriminator")]
+pub struct Discriminator {
+    #[serde(default)]
+    pub mapping: ::std::collections::BTreeMap<String, String>,
+    #[serde(rename = "propertyName")]
+    pub property_name: String,
+}
 pub type PositiveInteger = i64;
 pub type PositiveIntegerDefault0 = serde_json::Value;
 pub type SchemaArray = Vec<Schema>;
@@ -20,8 +29,60 @@ pub enum SimpleTypes {
     String,
 }
 pub type StringArray = Vec<String>;
+pub type SchemaAdditionalItemsVariant0 = bool;
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SchemaAdditionalItems {
+    Variant0(SchemaAdditionalItemsVariant0),
+    Variant1(Box<Schema>),
+}
+pub type SchemaAdditionalPropertiesVariant0 = bool;
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SchemaAdditionalProperties {
+    Variant0(SchemaAdditionalPropertiesVariant0),
+    Variant1(Box<Schema>),
+}
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SchemaDependencies {
+    Variant0(Box<Schema>),
+    Variant1(StringArray),
+}
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SchemaItems {
+    Variant0(SchemaArray),
+    Variant1(Box<Schema>),
+}
+pub type SchemaUnevaluatedItemsVariant0 = bool;
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SchemaUnevaluatedItems {
+    Variant0(SchemaUnevaluatedItemsVariant0),
+    Variant1(Box<Schema>),
+}
+pub type SchemaUnevaluatedPropertiesVariant0 = bool;
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SchemaUnevaluatedProperties {
+    Variant0(SchemaUnevaluatedPropertiesVariant0),
+    Variant1(Box<Schema>),
+}
 #[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
 pub struct Schema {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$anchor")]
+    pub anchor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$comment")]
+    pub comment: Option<String>,
+    #[serde(default)]
+    #[serde(rename = "$defs")]
+    pub defs: ::std::collections::BTreeMap<String, Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$id")]
+    pub id_: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "$ref")]
     pub ref_: Option<String>,
@@ -30,10 +91,10 @@ pub struct Schema {
     pub schema: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "additionalItems")]
-    pub additional_items: Option<serde_json::Value>,
+    pub additional_items: Option<SchemaAdditionalItems>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "additionalProperties")]
-    pub additional_properties: Option<serde_json::Value>,
+    pub additional_properties: Option<SchemaAdditionalProperties>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "allOf")]
     pub all_of: Option<SchemaArray>,
@@ -41,32 +102,50 @@ pub struct Schema {
     #[serde(rename = "anyOf")]
     pub any_of: Option<SchemaArray>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "const")]
+    pub const_: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contains: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<serde_json::Value>,
     #[serde(default)]
     pub definitions: ::std::collections::BTreeMap<String, Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub dependencies: Option<::std::collections::BTreeMap<String, serde_json::Value>>,
+    pub dependencies: Option<::std::collections::BTreeMap<String, SchemaDependencies>>,
+    #[serde(default)]
+    #[serde(rename = "dependentSchemas")]
+    pub dependent_schemas: ::std::collections::BTreeMap<String, Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub discriminator: Option<Discriminator>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "else")]
+    pub else_: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "enum")]
     pub enum_: Option<Vec<serde_json::Value>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "enumNames")]
     pub enum_names: Option<StringArray>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub examples: Option<Vec<serde_json::Value>>,
+    #[serde(default = "Schema::default_exclusive_maximum")]
     #[serde(rename = "exclusiveMaximum")]
-    pub exclusive_maximum: Option<bool>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exclusive_maximum: bool,
+    #[serde(default = "Schema::default_exclusive_minimum")]
     #[serde(rename = "exclusiveMinimum")]
-    pub exclusive_minimum: Option<bool>,
+    pub exclusive_minimum: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub format: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
-    #[serde(default)]
-    #[serde(with = "::schemafy_core::one_or_many")]
-    pub items: Vec<Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "if")]
+    pub if_: Option<Box<Schema>>,
+    #[doc = " The schema of every item, or the schemas of the items by position"]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub items: Option<SchemaItems>,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "maxItems")]
     pub max_items: Option<PositiveInteger>,
@@ -102,17 +181,113 @@ pub struct Schema {
     #[serde(default)]
     #[serde(rename = "patternProperties")]
     pub pattern_properties: ::std::collections::BTreeMap<String, Schema>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "prefixItems")]
+    pub prefix_items: Option<SchemaArray>,
     #[serde(default)]
     pub properties: ::std::collections::BTreeMap<String, Schema>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "propertyNames")]
+    pub property_names: Option<Box<Schema>>,
+    #[serde(default = "Schema::default_read_only")]
+    #[serde(rename = "readOnly")]
+    pub read_only: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub required: Option<StringArray>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub then: Option<Box<Schema>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
     #[serde(default)]
     #[serde(with = "::schemafy_core::one_or_many")]
     #[serde(rename = "type")]
     pub type_: Vec<SimpleTypes>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "unevaluatedItems")]
+    pub unevaluated_items: Option<SchemaUnevaluatedItems>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "unevaluatedProperties")]
+    pub unevaluated_properties: Option<SchemaUnevaluatedProperties>,
+    #[serde(default = "Schema::default_unique_items")]
     #[serde(rename = "uniqueItems")]
-    pub unique_items: Option<bool>,
+    pub unique_items: bool,
+    #[serde(default = "Schema::default_write_only")]
+    #[serde(rename = "writeOnly")]
+    pub write_only: bool,
+}
+impl Schema {
+    pub fn default_exclusive_maximum() -> bool {
+        false
+    }
+    pub fn default_exclusive_minimum() -> bool {
+        false
+    }
+    pub fn default_read_only() -> bool {
+        false
+    }
+    pub fn default_unique_items() -> bool {
+        false
+    }
+    pub fn default_write_only() -> bool {
+        false
+    }
+}
+impl Default for Schema {
+    fn default() -> Self {
+        Schema {
+            anchor: Default::default(),
+            comment: Default::default(),
+            defs: Default::default(),
+            id_: Default::default(),
+            ref_: Default::default(),
+            schema: Default::default(),
+            additional_items: Default::default(),
+            additional_properties: Default::default(),
+            all_of: Default::default(),
+            any_of: Default::default(),
+            const_: Default::default(),
+            contains: Default::default(),
+            default: Default::default(),
+            definitions: Default::default(),
+            dependencies: Default::default(),
+            dependent_schemas: Default::default(),
+            description: Default::default(),
+            discriminator: Default::default(),
+            else_: Default::default(),
+            enum_: Default::default(),
+            enum_names: Default::default(),
+            examples: Default::default(),
+            exclusive_maximum: Self::default_exclusive_maximum(),
+            exclusive_minimum: Self::default_exclusive_minimum(),
+            format: Default::default(),
+            id: Default::default(),
+            if_: Default::default(),
+            items: Default::default(),
+            max_items: Default::default(),
+            max_length: Default::default(),
+            max_properties: Default::default(),
+            maximum: Default::default(),
+            min_items: Default::default(),
+            min_length: Default::default(),
+            min_properties: Default::default(),
+            minimum: Default::default(),
+            multiple_of: Default::default(),
+            not: Default::default(),
+            one_of: Default::default(),
+            pattern: Default::default(),
+            pattern_properties: Default::default(),
+            prefix_items: Default::default(),
+            properties: Default::default(),
+            property_names: Default::default(),
+            read_only: Self::default_read_only(),
+            required: Default::default(),
+            then: Default::default(),
+            title: Default::default(),
+            type_: Default::default(),
+            unevaluated_items: Default::default(),
+            unevaluated_properties: Default::default(),
+            unique_items: Self::default_unique_items(),
+            write_only: Self::default_write_only(),
+        }
+    }
 }
@@ -0,0 +1,1014 @@
+//! Validation of JSON instances against a [`Schema`] at runtime, following
+//! the rules of draft 4. Of the keywords of later drafts only `const` is
+//! checked, the others are reported as unsupported.
+
+use std::{
+    cell::{RefCell, RefMut},
+    collections::HashMap,
+    fmt,
+    rc::Rc,
+};
+
+use regex::Regex;
+use schemafy_core::validate;
+use serde_json::Value;
+
+use crate::{
+    pointer, resolver, FileResolver, Resolver, Schema, SchemaDependencies, SimpleTypes, Uri,
+};
+
+/// The keywords of later drafts which [`Schema`] keeps but the [`Validator`]
+/// does not check.
+const UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "contains",
+    "dependentSchemas",
+    "else",
+    "if",
+    "prefixItems",
+    "propertyNames",
+    "then",
+    "unevaluatedItems",
+    "unevaluatedProperties",
+];
+
+/// How many `$ref`s may be followed without descending into the instance
+/// before the schema is considered to be endlessly recursive.
+const MAX_REF_DEPTH: usize = 64;
+
+/// A part of an instance which does not satisfy a keyword of the schema.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationError {
+    instance_path: String,
+    schema_path: String,
+    message: String,
+}
+
+impl ValidationError {
+    /// The JSON pointer to the invalid value within the instance.
+    pub fn instance_path(&self) -> &str {
+        &self.instance_path
+    }
+
+    /// The JSON pointer to the keyword the value violates, within the root
+    /// schema. References are followed through their `$ref` keyword.
+    pub fn schema_path(&self) -> &str {
+        &self.schema_path
+    }
+
+    /// The description of the error, without its location.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (schema {}): {}",
+            self.instance_path, self.schema_path, self.message
+        )
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Validates JSON instances against a schema.
+///
+/// The keywords of draft 4 are checked, as well as `const`. Schemas of later
+/// drafts need to be normalized with [`Dialect::normalize`](crate::Dialect::normalize)
+/// before they are deserialized, which turns most of their keywords into the
+/// draft 4 ones. The remaining keywords, such as `if` or `contains`, are not
+/// checked but fail the validation of every instance, so that no instance is
+/// taken for valid without having been checked against them.
+///
+/// References are resolved like the [`Expander`](crate::Expander) resolves
+/// them: against the base URI of the schema containing them, as changed by
+/// `id`, with the documents they point into loaded through the resolver.
+///
+/// ```rust
+/// use schemafy_lib::{Schema, Validator};
+/// use serde_json::json;
+///
+/// let schema: Schema = serde_json::from_value(json!({
+///     "properties": { "port": { "type": "integer", "maximum": 65535 } }
+/// }))
+/// .unwrap();
+/// let validator = Validator::new(&schema);
+/// assert!(validator.is_valid(&json!({ "port": 80 })));
+///
+/// let errors = validator.validate(&json!({ "port": 80000 })).unwrap_err();
+/// assert_eq!(errors[0].instance_path(), "/port");
+/// assert_eq!(errors[0].schema_path(), "/properties/port/maximum");
+/// ```
+pub struct Validator<'s> {
+    root: &'s Schema,
+    /// The URI of the root schema, relative references are resolved against it
+    base_uri: Option<Uri>,
+    /// Provides the documents referenced from the root schema
+    resolver: &'s dyn Resolver,
+    /// Documents other than the root schema which have been loaded through `$ref`
+    documents: RefCell<HashMap<Uri, Rc<Schema>>>,
+    /// The schemas in the root schema and the loaded documents which can be
+    /// referenced by `id` or by anchor
+    index: RefCell<resolver::Index>,
+    /// The compiled `pattern`s and `patternProperties`, `None` if invalid
+    regexes: RefCell<HashMap<String, Option<Regex>>>,
+}
+
+impl<'s> Validator<'s> {
+    pub fn new(root: &'s Schema) -> Self {
+        Validator {
+            root,
+            base_uri: None,
+            resolver: &FileResolver,
+            documents: RefCell::default(),
+            index: RefCell::default(),
+            regexes: RefCell::default(),
+        }
+    }
+
+    /// Sets the URI of the root schema, which relative references to other
+    /// schema documents are resolved against. Defaults to the current
+    /// directory.
+    pub fn with_base_uri(mut self, base_uri: Uri) -> Self {
+        self.base_uri = Some(base_uri);
+        self
+    }
+
+    /// Sets the resolver which provides the documents referenced from the root
+    /// schema. Defaults to [`FileResolver`].
+    pub fn with_resolver(mut self, resolver: &'s dyn Resolver) -> Self {
+        self.resolver = resolver;
+        self
+    }
+
+    /// Validates `instance`, returning every violation found.
+    pub fn validate(&self, instance: &Value) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut at = At {
+            instance_path: String::new(),
+            schema_path: String::new(),
+            scope: self.root_uri(),
+            ref_depth: 0,
+        };
+        self.check(self.root, instance, &mut at, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        self.validate(instance).is_ok()
+    }
+
+    fn root_uri(&self) -> Uri {
+        self.base_uri
+            .clone()
+            .unwrap_or_else(resolver::current_dir_uri)
+    }
+
+    fn check(
+        &self,
+        schema: &Schema,
+        instance: &Value,
+        at: &mut At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(ref ref_) = schema.ref_ {
+            // Up to draft 7 the other keywords next to `$ref`, `id` included,
+            // are ignored
+            return at.keyword(&["$ref"], |at| self.check_ref(ref_, instance, at, errors));
+        }
+        match resolver::scope(&at.scope, schema) {
+            Some(scope) => {
+                let parent = std::mem::replace(&mut at.scope, scope);
+                self.check_keywords(schema, instance, at, errors);
+                at.scope = parent;
+            }
+            None => self.check_keywords(schema, instance, at, errors),
+        }
+    }
+
+    fn check_keywords(
+        &self,
+        schema: &Schema,
+        instance: &Value,
+        at: &mut At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for &keyword in UNSUPPORTED_KEYWORDS {
+            if has_keyword(schema, keyword) {
+                errors.push(at.error(
+                    &[keyword],
+                    format!("The keyword `{}` is not supported", keyword),
+                ));
+            }
+        }
+        if !schema.type_.is_empty() && !schema.type_.iter().any(|t| has_type(instance, t)) {
+            errors.push(at.error(
+                &["type"],
+                format!("{} is not of type {:?}", instance, schema.type_),
+            ));
+        }
+        if let Some(ref values) = schema.enum_ {
+            if !values.iter().any(|value| json_equal(value, instance)) {
+                errors.push(at.error(&["enum"], format!("{} is not one of the values", instance)));
+            }
+        }
+        if let Some(ref value) = schema.const_ {
+            if !json_equal(value, instance) {
+                errors.push(at.error(&["const"], format!("{} is not {}", instance, value)));
+            }
+        }
+
+        match *instance {
+            Value::Number(ref number) => {
+                self.check_number(schema, number.as_f64().unwrap_or(f64::NAN), at, errors)
+            }
+            Value::String(ref string) => self.check_string(schema, string, at, errors),
+            Value::Array(ref items) => self.check_array(schema, items, at, errors),
+            Value::Object(ref object) => self.check_object(schema, object, at, errors),
+            _ => (),
+        }
+
+        if let Some(ref all_of) = schema.all_of {
+            for (i, subschema) in all_of.iter().enumerate() {
+                at.keyword(&["allOf", &i.to_string()], |at| {
+                    self.check(subschema, instance, at, errors)
+                });
+            }
+        }
+        if let Some(ref any_of) = schema.any_of {
+            let valid = self.count_valid(any_of, "anyOf", instance, at);
+            if valid == 0 {
+                errors.push(at.error(
+                    &["anyOf"],
+                    format!("{} is valid under none of the schemas", instance),
+                ));
+            }
+        }
+        if let Some(ref one_of) = schema.one_of {
+            let valid = self.count_valid(one_of, "oneOf", instance, at);
+            if valid != 1 {
+                errors.push(at.error(
+                    &["oneOf"],
+                    format!(
+                        "{} is valid under {} of the schemas, not exactly one",
+                        instance, valid
+                    ),
+                ));
+            }
+        }
+        if let Some(ref not) = schema.not {
+            let mut not_errors = Vec::new();
+            at.keyword(&["not"], |at| {
+                self.check(not, instance, at, &mut not_errors)
+            });
+            if not_errors.is_empty() {
+                errors.push(at.error(&["not"], format!("{} is valid under the schema", instance)));
+            }
+        }
+    }
+
+    fn check_ref(
+        &self,
+        ref_: &str,
+        instance: &Value,
+        at: &mut At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let (document, tokens) = match self.locate(ref_, &at.scope) {
+            Ok(location) => location,
+            Err(message) => {
+                errors.push(at.error(&[], message));
+                return;
+            }
+        };
+        if at.ref_depth >= MAX_REF_DEPTH {
+            errors.push(at.error(&[], format!("The reference `{}` recurses endlessly", ref_)));
+            return;
+        }
+        let loaded;
+        let (document_schema, mut scope) = match document {
+            Some(uri) => {
+                loaded = self.documents.borrow()[&uri].clone();
+                (&*loaded, uri)
+            }
+            None => (self.root, self.root_uri()),
+        };
+        // The `id`s on the way to the target change the base URI of its
+        // references, its own `id` is taken into account by `check`
+        let target = pointer::resolve_with(document_schema, &tokens, |schema| {
+            if let Some(uri) = resolver::scope(&scope, schema) {
+                scope = uri;
+            }
+        });
+        let target = match target {
+            Some(target) => target,
+            None => {
+                errors.push(at.error(&[], format!("Unresolvable reference: `{}`", ref_)));
+                return;
+            }
+        };
+        let parent = std::mem::replace(&mut at.scope, scope);
+        at.ref_depth += 1;
+        self.check(&target, instance, at, errors);
+        at.ref_depth -= 1;
+        at.scope = parent;
+    }
+
+    /// Returns the document (`None` for the root schema) and the reference
+    /// tokens of the schema which `ref_` points at from within `scope`,
+    /// loading the document on first use.
+    fn locate(&self, ref_: &str, scope: &Uri) -> Result<resolver::Location, String> {
+        let unresolvable = || format!("Unresolvable reference: `{}`", ref_);
+        let (uri, fragment) = resolver::resolve_reference(scope, ref_).ok_or_else(unresolvable)?;
+        if self.index().resource(&uri).is_none() {
+            self.load(&uri)?;
+        }
+        let index = self.index();
+        let location = match pointer::tokens(&fragment) {
+            Some(tokens) => index.resource(&uri).map(|(document, base)| {
+                let mut path = base.clone();
+                path.extend(tokens);
+                (document.clone(), path)
+            }),
+            None => index.anchor(&uri, &fragment).cloned(),
+        };
+        location.ok_or_else(unresolvable)
+    }
+
+    /// Returns the index of the schemas which can be referenced by `id` or by
+    /// anchor, indexing the root schema on first use.
+    fn index(&self) -> RefMut<'_, resolver::Index> {
+        let mut index = self.index.borrow_mut();
+        if index.is_empty() {
+            index.add_document(None, self.root_uri(), self.root);
+        }
+        index
+    }
+
+    /// Loads the document identified by `uri` through the resolver.
+    fn load(&self, uri: &Uri) -> Result<(), String> {
+        let schema = resolver::load_document(self.resolver, uri, &resolver::display_uri(uri))
+            .map_err(|err| err.to_string())?;
+        self.index()
+            .add_document(Some(uri.clone()), uri.clone(), &schema);
+        self.documents
+            .borrow_mut()
+            .insert(uri.clone(), Rc::new(schema));
+        Ok(())
+    }
+
+    /// Counts the `schemas` of the `keyword` union which `instance` is valid
+    /// under.
+    fn count_valid(
+        &self,
+        schemas: &[Schema],
+        keyword: &str,
+        instance: &Value,
+        at: &mut At,
+    ) -> usize {
+        schemas
+            .iter()
+            .enumerate()
+            .filter(|&(i, subschema)| {
+                let mut errors = Vec::new();
+                at.keyword(&[keyword, &i.to_string()], |at| {
+                    self.check(subschema, instance, at, &mut errors)
+                });
+                errors.is_empty()
+            })
+            .count()
+    }
+
+    fn check_number(
+        &self,
+        schema: &Schema,
+        number: f64,
+        at: &At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(minimum) = schema.minimum {
+            if schema.exclusive_minimum && number <= minimum {
+                errors.push(at.error(
+                    &["exclusiveMinimum"],
+                    format!("{} is not greater than {}", number, minimum),
+                ));
+            } else if number < minimum {
+                errors.push(at.error(&["minimum"], format!("{} is less than {}", number, minimum)));
+            }
+        }
+        if let Some(maximum) = schema.maximum {
+            if schema.exclusive_maximum && number >= maximum {
+                errors.push(at.error(
+                    &["exclusiveMaximum"],
+                    format!("{} is not less than {}", number, maximum),
+                ));
+            } else if number > maximum {
+                errors.push(at.error(
+                    &["maximum"],
+                    format!("{} is greater than {}", number, maximum),
+                ));
+            }
+        }
+        if let Some(multiple_of) = schema.multiple_of {
+            let mut violations = validate::ValidationErrors::new();
+            validate::multiple_of(number, multiple_of, "", &mut violations);
+            errors.extend(
+                violations
+                    .iter()
+                    .map(|violation| at.error(&["multipleOf"], violation.message())),
+            );
+        }
+    }
+
+    fn check_string(
+        &self,
+        schema: &Schema,
+        string: &str,
+        at: &At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let length = string.chars().count();
+        if let Some(min_length) = schema.min_length.as_ref().and_then(Value::as_u64) {
+            if (length as u64) < min_length {
+                errors.push(at.error(
+                    &["minLength"],
+                    format!("{:?} is shorter than {} characters", string, min_length),
+                ));
+            }
+        }
+        if let Some(max_length) = schema.max_length {
+            if length as i64 > max_length {
+                errors.push(at.error(
+                    &["maxLength"],
+                    format!("{:?} is longer than {} characters", string, max_length),
+                ));
+            }
+        }
+        if let Some(ref pattern) = schema.pattern {
+            match self.is_match(pattern, string) {
+                Some(true) => (),
+                Some(false) => errors.push(at.error(
+                    &["pattern"],
+                    format!("{:?} does not match `{}`", string, pattern),
+                )),
+                None => errors.push(at.error(
+                    &["pattern"],
+                    format!("`{}` is not a supported regular expression", pattern),
+                )),
+            }
+        }
+    }
+
+    fn check_array(
+        &self,
+        schema: &Schema,
+        items: &[Value],
+        at: &mut At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(min_items) = schema.min_items.as_ref().and_then(Value::as_u64) {
+            if (items.len() as u64) < min_items {
+                errors.push(at.error(
+                    &["minItems"],
+                    format!("{} items are fewer than {}", items.len(), min_items),
+                ));
+            }
+        }
+        if let Some(max_items) = schema.max_items {
+            if items.len() as i64 > max_items {
+                errors.push(at.error(
+                    &["maxItems"],
+                    format!("{} items are more than {}", items.len(), max_items),
+                ));
+            }
+        }
+        if schema.unique_items {
+            let duplicate = items
+                .iter()
+                .enumerate()
+                .any(|(i, item)| items[..i].iter().any(|other| json_equal(item, other)));
+            if duplicate {
+                errors.push(at.error(&["uniqueItems"], "the items are not unique"));
+            }
+        }
+
+        match schema.items.as_ref().map(|items| items.as_schema()) {
+            None => (),
+            Some(Ok(item)) => {
+                for (i, instance) in items.iter().enumerate() {
+                    at.item(&i.to_string(), &["items"], |at| {
+                        self.check(item, instance, at, errors)
+                    });
+                }
+            }
+            Some(Err(tuple)) => {
+                for (i, (item, instance)) in tuple.iter().zip(items).enumerate() {
+                    let index = i.to_string();
+                    at.item(&index, &["items", &index], |at| {
+                        self.check(item, instance, at, errors)
+                    });
+                }
+                match schema.additional_items.as_ref().map(|a| a.as_schema()) {
+                    Some(Ok(additional)) => {
+                        for (i, instance) in items.iter().enumerate().skip(tuple.len()) {
+                            at.item(&i.to_string(), &["additionalItems"], |at| {
+                                self.check(additional, instance, at, errors)
+                            });
+                        }
+                    }
+                    Some(Err(false)) if items.len() > tuple.len() => {
+                        errors.push(at.error(
+                            &["additionalItems"],
+                            format!("{} items are more than {}", items.len(), tuple.len()),
+                        ));
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        schema: &Schema,
+        object: &serde_json::Map<String, Value>,
+        at: &mut At,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if let Some(min_properties) = schema.min_properties.as_ref().and_then(Value::as_u64) {
+            if (object.len() as u64) < min_properties {
+                errors.push(at.error(
+                    &["minProperties"],
+                    format!(
+                        "{} properties are fewer than {}",
+                        object.len(),
+                        min_properties
+                    ),
+                ));
+            }
+        }
+        if let Some(max_properties) = schema.max_properties {
+            if object.len() as i64 > max_properties {
+                errors.push(at.error(
+                    &["maxProperties"],
+                    format!(
+                        "{} properties are more than {}",
+                        object.len(),
+                        max_properties
+                    ),
+                ));
+            }
+        }
+        for required in schema.required.iter().flatten() {
+            if !object.contains_key(required) {
+                errors.push(at.error(&["required"], format!("`{}` is required", required)));
+            }
+        }
+
+        for (key, value) in object {
+            let mut additional = true;
+            if let Some(property) = schema.properties.get(key) {
+                additional = false;
+                at.item(key, &["properties", key], |at| {
+                    self.check(property, value, at, errors)
+                });
+            }
+            for (pattern, property) in &schema.pattern_properties {
+                if self.is_match(pattern, key) == Some(true) {
+                    additional = false;
+                    at.item(key, &["patternProperties", pattern], |at| {
+                        self.check(property, value, at, errors)
+                    });
+                }
+            }
+            if !additional {
+                continue;
+            }
+            match schema.additional_properties.as_ref().map(|a| a.as_schema()) {
+                Some(Ok(property)) => at.item(key, &["additionalProperties"], |at| {
+                    self.check(property, value, at, errors)
+                }),
+                Some(Err(false)) => errors.push(at.error(
+                    &["additionalProperties"],
+                    format!("`{}` is not an allowed property", key),
+                )),
+                _ => (),
+            }
+        }
+
+        for (property, dependency) in schema.dependencies.iter().flatten() {
+            if !object.contains_key(property) {
+                continue;
+            }
+            match *dependency {
+                SchemaDependencies::Variant0(ref dependency) => {
+                    let instance = Value::Object(object.clone());
+                    at.keyword(&["dependencies", property], |at| {
+                        self.check(dependency, &instance, at, errors)
+                    });
+                }
+                SchemaDependencies::Variant1(ref required) => {
+                    for required in required {
+                        if !object.contains_key(required) {
+                            errors.push(at.error(
+                                &["dependencies", property],
+                                format!("`{}` is required by `{}`", required, property),
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether `pattern` matches `string`, `None` if the pattern is not a
+    /// regular expression the `regex` crate supports.
+    fn is_match(&self, pattern: &str, string: &str) -> Option<bool> {
+        self.regexes
+            .borrow_mut()
+            .entry(pattern.to_owned())
+            .or_insert_with(|| Regex::new(pattern).ok())
+            .as_ref()
+            .map(|regex| regex.is_match(string))
+    }
+}
+
+/// The location of the value and the schema being checked.
+struct At {
+    instance_path: String,
+    schema_path: String,
+    /// The base URI which references are resolved against
+    scope: Uri,
+    /// The number of `$ref`s followed since the last step into the instance
+    ref_depth: usize,
+}
+
+impl At {
+    fn error(&self, keyword: &[&str], message: impl Into<String>) -> ValidationError {
+        ValidationError {
+            instance_path: self.instance_path.clone(),
+            schema_path: format!("{}{}", self.schema_path, pointer(keyword)),
+            message: message.into(),
+        }
+    }
+
+    /// Runs `f` at the subschema behind `keyword`.
+    fn keyword<T>(&mut self, keyword: &[&str], f: impl FnOnce(&mut At) -> T) -> T {
+        let len = self.schema_path.len();
+        self.schema_path.push_str(&pointer(keyword));
+        let result = f(self);
+        self.schema_path.truncate(len);
+        result
+    }
+
+    /// Runs `f` at the item or property `token` of the instance and the
+    /// subschema behind `keyword`.
+    fn item<T>(&mut self, token: &str, keyword: &[&str], f: impl FnOnce(&mut At) -> T) -> T {
+        let len = self.instance_path.len();
+        self.instance_path.push_str(&pointer(&[token]));
+        let ref_depth = std::mem::replace(&mut self.ref_depth, 0);
+        let result = self.keyword(keyword, f);
+        self.ref_depth = ref_depth;
+        self.instance_path.truncate(len);
+        result
+    }
+}
+
+fn pointer(tokens: &[&str]) -> String {
+    pointer::pointer(
+        &tokens
+            .iter()
+            .map(|&token| token.to_owned())
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn has_type(instance: &Value, typ: &SimpleTypes) -> bool {
+    match (typ, instance) {
+        (SimpleTypes::Array, Value::Array(_))
+        | (SimpleTypes::Boolean, Value::Bool(_))
+        | (SimpleTypes::Null, Value::Null)
+        | (SimpleTypes::Number, Value::Number(_))
+        | (SimpleTypes::Object, Value::Object(_))
+        | (SimpleTypes::String, Value::String(_)) => true,
+        (SimpleTypes::Integer, Value::Number(number)) => {
+            number.is_i64()
+                || number.is_u64()
+                || number.as_f64().map_or(false, |n| n.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `schema` has the keyword, one of [`UNSUPPORTED_KEYWORDS`].
+fn has_keyword(schema: &Schema, keyword: &str) -> bool {
+    match keyword {
+        "contains" => schema.contains.is_some(),
+        "dependentSchemas" => !schema.dependent_schemas.is_empty(),
+        "else" => schema.else_.is_some(),
+        "if" => schema.if_.is_some(),
+        "prefixItems" => schema.prefix_items.is_some(),
+        "propertyNames" => schema.property_names.is_some(),
+        "then" => schema.then.is_some(),
+        "unevaluatedItems" => schema.unevaluated_items.is_some(),
+        "unevaluatedProperties" => schema.unevaluated_properties.is_some(),
+        _ => false,
+    }
+}
+
+/// Compares JSON values with numbers compared by their value, so `1` equals
+/// `1.0`.
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => match (a.as_u64(), b.as_u64()) {
+                (Some(a), Some(b)) => a == b,
+                _ => a.as_f64() == b.as_f64(),
+            },
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| json_equal(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len()
+                && a.iter()
+                    .all(|(key, a)| b.get(key).map_or(false, |b| json_equal(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::convert::TryFrom;
+
+    use serde_json::json;
+
+    fn schema(value: Value) -> Schema {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn errors(schema: &Schema, instance: Value) -> Vec<(String, String)> {
+        Validator::new(schema)
+            .validate(&instance)
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|error| (error.instance_path, error.schema_path))
+            .collect()
+    }
+
+    #[test]
+    fn locate_errors() {
+        let schema = schema(json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "tags": { "type": "array", "items": { "$ref": "#/definitions/Tag" } }
+            },
+            "additionalProperties": false,
+            "definitions": {
+                "Tag": { "type": "string", "maxLength": 3 }
+            }
+        }));
+        assert_eq!(
+            errors(&schema, json!({ "tags": ["a", "long", 1], "other": 1 })),
+            [
+                ("".to_owned(), "/required".to_owned()),
+                ("".to_owned(), "/additionalProperties".to_owned()),
+                (
+                    "/tags/1".to_owned(),
+                    "/properties/tags/items/$ref/maxLength".to_owned()
+                ),
+                (
+                    "/tags/2".to_owned(),
+                    "/properties/tags/items/$ref/type".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn combinators() {
+        let schema = schema(json!({
+            "oneOf": [{ "type": "integer" }, { "minimum": 2 }],
+            "not": { "enum": [5.0] }
+        }));
+        let validator = Validator::new(&schema);
+        assert!(validator.is_valid(&json!(1)));
+        assert!(validator.is_valid(&json!(2.5)));
+        // Valid under both schemas
+        assert!(!validator.is_valid(&json!(3)));
+        assert!(!validator.is_valid(&json!(5)));
+    }
+
+    #[test]
+    fn endless_reference() {
+        let schema = schema(json!({ "$ref": "#" }));
+        assert_eq!(
+            Validator::new(&schema).validate(&json!(1)).unwrap_err()[0].message(),
+            "The reference `#` recurses endlessly"
+        );
+    }
+
+    #[test]
+    fn one_element_tuple() {
+        let schema = schema(json!({
+            "$schema": "http://json-schema.org/draft-04/schema#",
+            "items": [{ "type": "integer" }],
+            "additionalItems": { "type": "string" }
+        }));
+        assert_eq!(errors(&schema, json!([1, "a", "b"])), []);
+        assert_eq!(
+            errors(&schema, json!(["a", 1])),
+            [
+                ("/0".to_owned(), "/items/0/type".to_owned()),
+                ("/1".to_owned(), "/additionalItems/type".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unique_numbers() {
+        let schema = schema(json!({ "uniqueItems": true }));
+        let validator = Validator::new(&schema);
+        assert!(!validator.is_valid(&json!([1, 1.0])));
+        assert!(validator.is_valid(&json!([{ "a": 1 }, { "a": 2 }])));
+    }
+
+    #[test]
+    fn dependencies() {
+        let schema = schema(json!({
+            "dependencies": {
+                "bar": ["foo"],
+                "baz": { "properties": { "foo": { "type": "integer" } } }
+            }
+        }));
+        assert_eq!(errors(&schema, json!({ "foo": 1, "bar": 2, "baz": 3 })), []);
+        assert_eq!(errors(&schema, json!({ "foo": "a" })), []);
+        assert_eq!(
+            errors(&schema, json!({ "bar": 2 })),
+            [("".to_owned(), "/dependencies/bar".to_owned())]
+        );
+        assert_eq!(
+            errors(&schema, json!({ "foo": "a", "baz": 3 })),
+            [(
+                "/foo".to_owned(),
+                "/dependencies/baz/properties/foo/type".to_owned()
+            )]
+        );
+    }
+
+    #[test]
+    fn pattern_properties() {
+        let schema = schema(json!({
+            "properties": { "id": { "type": "string" } },
+            "patternProperties": { "^x-": { "type": "integer" } },
+            "additionalProperties": false
+        }));
+        assert_eq!(errors(&schema, json!({ "id": "a", "x-size": 1 })), []);
+        assert_eq!(
+            errors(&schema, json!({ "x-size": "a", "other": 1 })),
+            [
+                ("".to_owned(), "/additionalProperties".to_owned()),
+                (
+                    "/x-size".to_owned(),
+                    "/patternProperties/^x-/type".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn property_counts() {
+        let schema = schema(json!({ "minProperties": 1, "maxProperties": 2 }));
+        assert_eq!(errors(&schema, json!({ "a": 1 })), []);
+        assert_eq!(
+            errors(&schema, json!({})),
+            [("".to_owned(), "/minProperties".to_owned())]
+        );
+        assert_eq!(
+            errors(&schema, json!({ "a": 1, "b": 2, "c": 3 })),
+            [("".to_owned(), "/maxProperties".to_owned())]
+        );
+        // Only objects are counted
+        assert_eq!(errors(&schema, json!([])), []);
+    }
+
+    #[test]
+    fn invalid_pattern() {
+        let schema = schema(json!({ "pattern": "(" }));
+        let errors = Validator::new(&schema).validate(&json!("a")).unwrap_err();
+        assert_eq!(errors[0].schema_path(), "/pattern");
+        assert_eq!(
+            errors[0].message(),
+            "`(` is not a supported regular expression"
+        );
+    }
+
+    #[test]
+    fn constant() {
+        let schema = schema(json!({ "const": { "a": [1] } }));
+        let validator = Validator::new(&schema);
+        assert!(validator.is_valid(&json!({ "a": [1.0] })));
+        assert!(!validator.is_valid(&json!({ "a": [2] })));
+    }
+
+    #[test]
+    fn unsupported_keywords() {
+        let mut value = json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "properties": {
+                "tags": { "type": "array", "contains": { "const": "new" } },
+                "card": { "if": { "type": "string" }, "then": { "minLength": 16 } }
+            },
+            "dependentRequired": { "card": ["billing"] }
+        });
+        crate::Dialect::detect(&value).normalize(&mut value);
+        let schema = schema(value);
+
+        assert_eq!(
+            errors(
+                &schema,
+                json!({ "tags": ["used"], "card": "1234", "billing": {} })
+            ),
+            vec![
+                ("/card".into(), "/properties/card/if".into()),
+                ("/card".into(), "/properties/card/then".into()),
+                ("/tags".into(), "/properties/tags/contains".into()),
+            ]
+        );
+        // Keywords folded into those of draft 4 are checked
+        assert_eq!(
+            errors(&schema, json!({ "card": 1234 })),
+            vec![
+                ("/card".into(), "/properties/card/if".into()),
+                ("/card".into(), "/properties/card/then".into()),
+                ("".into(), "/dependencies/card".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn references_scoped_by_id() {
+        let schema = schema(json!({
+            "id": "http://localhost:1234/",
+            "items": {
+                "id": "folder/",
+                "items": { "$ref": "folderInteger.json" }
+            },
+            "definitions": {
+                "other": {
+                    "id": "http://localhost:1234/other.json",
+                    "definitions": { "name": { "type": "string" } }
+                },
+                "anchored": { "id": "#anchored", "type": "boolean" }
+            },
+            "properties": {
+                "name": { "$ref": "other.json#/definitions/name" },
+                "flag": { "$ref": "#anchored" }
+            }
+        }));
+        let remotes = vec![(
+            Uri::try_from("http://localhost:1234/folder/folderInteger.json")
+                .unwrap()
+                .into_owned(),
+            json!({ "type": "integer" }),
+        )]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+        let validator = Validator::new(&schema).with_resolver(&remotes);
+
+        assert!(validator.is_valid(&json!([[1]])));
+        assert_eq!(
+            validator.validate(&json!([["a"]])).unwrap_err()[0].schema_path(),
+            "/items/items/$ref/type"
+        );
+        assert!(validator.is_valid(&json!({ "name": "a", "flag": true })));
+        assert!(!validator.is_valid(&json!({ "name": 1 })));
+        assert!(!validator.is_valid(&json!({ "flag": 1 })));
+    }
+
+    #[test]
+    fn unknown_document() {
+        let schema = schema(json!({ "$ref": "http://localhost:1234/missing.json" }));
+        let remotes = HashMap::new();
+        let errors = Validator::new(&schema)
+            .with_resolver(&remotes)
+            .validate(&json!(1))
+            .unwrap_err();
+        assert_eq!(
+            errors[0].message(),
+            "Unable to read `http://localhost:1234/missing.json`: Unknown schema `http://localhost:1234/missing.json`"
+        );
+    }
+}
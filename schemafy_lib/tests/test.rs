@@ -1,4 +1,7 @@
-use schemafy_lib::Expander;
+use std::{collections::HashMap, convert::TryFrom};
+
+use schemafy_lib::{file_uri, Error, Expander, Generator, Schema, Uri};
+use serde_json::{json, Value};
 
 #[test]
 fn schema() {
@@ -40,3 +43,381 @@ fn test_str_to_ident() {
         Ident::new("thieves_tools", Span::call_site())
     );
 }
+
+fn uri(s: &str) -> Uri {
+    uriparse::URI::try_from(s).unwrap().into_owned()
+}
+
+/// Expands `schema` as the document `https://example.com/schemas/root.json`,
+/// with `documents` serving the documents it references.
+fn expand(schema: Value, documents: &HashMap<Uri, Value>) -> Result<syn::File, Error> {
+    let schema: Schema = serde_json::from_value(schema).unwrap();
+    let tokens = Expander::new(Some("Root"), "UNUSED", &schema)
+        .with_base_uri(uri("https://example.com/schemas/root.json"))
+        .with_resolver(documents)
+        .try_expand(&schema)?;
+    Ok(syn::parse2(tokens).unwrap())
+}
+
+fn structs<'f>(file: &'f syn::File, name: &'f str) -> impl Iterator<Item = &'f syn::ItemStruct> {
+    file.items.iter().filter_map(move |item| match item {
+        syn::Item::Struct(item) if item.ident == name => Some(item),
+        _ => None,
+    })
+}
+
+/// The type of the field `field` of the struct `name`.
+fn field_type(file: &syn::File, name: &str, field: &str) -> syn::Type {
+    let item = structs(file, name)
+        .next()
+        .unwrap_or_else(|| panic!("No struct `{}`", name));
+    item.fields
+        .iter()
+        .find(|f| f.ident.as_ref().map_or(false, |ident| ident == field))
+        .unwrap_or_else(|| panic!("No field `{}` in `{}`", field, name))
+        .ty
+        .clone()
+}
+
+/// The type of the single field of the variant `variant` of the enum `name`.
+fn variant_type(file: &syn::File, name: &str, variant: &str) -> syn::Type {
+    let item = file
+        .items
+        .iter()
+        .find_map(|item| match item {
+            syn::Item::Enum(item) if item.ident == name => Some(item),
+            _ => None,
+        })
+        .unwrap_or_else(|| panic!("No enum `{}`", name));
+    let variant = item
+        .variants
+        .iter()
+        .find(|v| v.ident == variant)
+        .unwrap_or_else(|| panic!("No variant `{}` in `{}`", variant, name));
+    variant.fields.iter().next().unwrap().ty.clone()
+}
+
+fn ty(s: &str) -> syn::Type {
+    syn::parse_str(s).unwrap()
+}
+
+#[test]
+fn in_memory_resolver() {
+    let mut documents = HashMap::new();
+    documents.insert(
+        uri("https://example.com/schemas/types.json"),
+        json!({
+            "definitions": {
+                "Person": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } }
+                }
+            }
+        }),
+    );
+    let file = expand(
+        json!({
+            "type": "object",
+            "properties": {
+                "owner": { "$ref": "types.json#/definitions/Person" }
+            }
+        }),
+        &documents,
+    )
+    .unwrap();
+
+    assert_eq!(field_type(&file, "Root", "owner"), ty("Option<Person>"));
+    assert_eq!(field_type(&file, "Person", "name"), ty("Option<String>"));
+}
+
+#[test]
+fn errors_are_located() {
+    let err = expand(
+        json!({
+            "definitions": {
+                "Size": { "enum": ["small", "large"], "enumNames": ["Small"] }
+            }
+        }),
+        &HashMap::new(),
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        err.document(),
+        Some(&uri("https://example.com/schemas/root.json"))
+    );
+    assert_eq!(err.pointer(), "/definitions/Size");
+    assert_eq!(
+        err.to_string(),
+        "https://example.com/schemas/root.json#/definitions/Size: enumNames(length 1) and enum(length 2) have different length"
+    );
+
+    let err = expand(
+        json!({
+            "properties": {
+                "tags": {
+                    "type": "array",
+                    "items": { "$ref": "#/definitions/Missing" }
+                }
+            }
+        }),
+        &HashMap::new(),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.pointer(), "/properties/tags/items");
+    assert_eq!(
+        err.message(),
+        "Unresolvable reference: `#/definitions/Missing`"
+    );
+}
+
+#[test]
+fn default_out_of_range() {
+    let err = expand(
+        json!({
+            "type": "object",
+            "properties": {
+                "level": { "type": "integer", "minimum": 0, "maximum": 255, "default": -1 }
+            }
+        }),
+        &HashMap::new(),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.pointer(), "/properties/level");
+    assert_eq!(err.message(), "The default `-1` is not a value of `u8`");
+}
+
+#[test]
+fn default_of_another_type() {
+    let err = expand(
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string", "default": 5 }
+            }
+        }),
+        &HashMap::new(),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.pointer(), "/properties/name");
+    assert_eq!(err.message(), "The default `5` is not a value of `String`");
+}
+
+#[test]
+fn track_referenced_files() {
+    let root = std::path::Path::new("../tests/external-refs/root.json")
+        .canonicalize()
+        .unwrap();
+    let tokens = Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file(&root)
+        .with_track_files(true)
+        .build()
+        .try_generate()
+        .unwrap();
+    let file: syn::File = syn::parse2(tokens).unwrap();
+
+    let tracked = file
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            syn::Item::Const(item) => match &*item.expr {
+                syn::Expr::Macro(expr) if expr.mac.path.is_ident("include_bytes") => {
+                    Some(expr.mac.parse_body::<syn::LitStr>().unwrap().value())
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let expected = ["common.json", "root.json", "types/person.json"]
+        .iter()
+        .map(|file| root.with_file_name(file).to_str().unwrap().to_owned())
+        .collect::<Vec<_>>();
+    assert_eq!(tracked, expected);
+}
+
+#[test]
+fn report_missing_input_file() {
+    let err = Generator::builder()
+        .with_input_file("tests/does-not-exist.json")
+        .build()
+        .try_generate()
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Unable to read `tests/does-not-exist.json`: "),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn errors_in_files_have_positions() {
+    let err = Generator::builder()
+        .with_root_name(Some("Root".to_owned()))
+        .with_input_file("tests/unresolvable-ref.json")
+        .build()
+        .try_generate()
+        .unwrap_err();
+    assert_eq!(err.pointer(), "/properties/tags/items");
+    assert_eq!(err.position(), Some((6, 16)));
+    assert!(
+        err.to_string()
+            .ends_with("unresolvable-ref.json:6:16 (#/properties/tags/items): Unresolvable reference: `#/definitions/Missing`"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn variants_named_after_ids() {
+    let file = expand(
+        json!({
+            "oneOf": [
+                { "$id": "http://example.com/schemas/point.json", "type": "object", "properties": { "x": { "type": "number" } } },
+                { "id": "http://example.com/schemas/", "type": "object", "properties": { "y": { "type": "number" } } },
+                { "$id": "http://example.com/schemas/point.json#", "type": "object", "properties": { "z": { "type": "number" } } }
+            ]
+        }),
+        &HashMap::new(),
+    )
+    .unwrap();
+
+    assert_eq!(variant_type(&file, "Root", "Point"), ty("RootPoint"));
+    assert_eq!(variant_type(&file, "Root", "Schemas"), ty("RootSchemas"));
+    assert_eq!(variant_type(&file, "Root", "Variant2"), ty("RootVariant2"));
+    assert_eq!(field_type(&file, "RootSchemas", "y"), ty("Option<f64>"));
+}
+
+#[test]
+fn same_named_definitions_in_documents() {
+    let mut documents = HashMap::new();
+    documents.insert(
+        uri("https://example.com/schemas/types.json"),
+        json!({
+            "definitions": {
+                "Address": {
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } }
+                },
+                "Person": {
+                    "type": "object",
+                    "properties": { "address": { "$ref": "#/definitions/Address" } }
+                }
+            }
+        }),
+    );
+    let file = expand(
+        json!({
+            "type": "object",
+            "properties": {
+                "home": { "$ref": "#/definitions/Address" },
+                "billing": { "$ref": "types.json#/definitions/Address" },
+                "owner": { "$ref": "types.json#/definitions/Person" }
+            },
+            "definitions": {
+                "Address": {
+                    "type": "object",
+                    "properties": { "street": { "type": "string" } }
+                }
+            }
+        }),
+        &documents,
+    )
+    .unwrap();
+
+    assert_eq!(structs(&file, "Address").count(), 1);
+    assert_eq!(field_type(&file, "Address", "street"), ty("Option<String>"));
+    assert_eq!(
+        field_type(&file, "TypesAddress", "city"),
+        ty("Option<String>")
+    );
+    assert_eq!(field_type(&file, "Root", "home"), ty("Option<Address>"));
+    assert_eq!(
+        field_type(&file, "Root", "billing"),
+        ty("Option<TypesAddress>")
+    );
+    assert_eq!(
+        field_type(&file, "Person", "address"),
+        ty("Option<TypesAddress>")
+    );
+}
+
+#[test]
+fn compare_generators() {
+    let documents: HashMap<Uri, Value> = HashMap::new();
+    assert_eq!(
+        Generator::builder().build(),
+        Generator::builder().with_resolver(&documents).build()
+    );
+    assert_ne!(
+        Generator::builder(),
+        Generator::builder().with_validate(true)
+    );
+}
+
+#[test]
+fn resolver_serves_input_file() {
+    let input_file = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("in-memory.json");
+    let mut documents = HashMap::new();
+    documents.insert(
+        file_uri(&input_file),
+        json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" } }
+        }),
+    );
+
+    let tokens = Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file(&input_file)
+        .with_resolver(&documents)
+        .build()
+        .try_generate()
+        .unwrap();
+    let file: syn::File = syn::parse2(tokens).unwrap();
+
+    assert_eq!(field_type(&file, "Root", "name"), ty("Option<String>"));
+}
+
+#[test]
+fn resolver_and_file_system_serve_references() {
+    let input_file =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/in-memory-ref.json");
+    let mut documents = HashMap::new();
+    documents.insert(
+        file_uri(&input_file.with_file_name("types.json")),
+        json!({
+            "definitions": {
+                "Person": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } }
+                }
+            }
+        }),
+    );
+
+    let tokens = Generator::builder()
+        .with_root_name_str("Root")
+        .with_input_file(&input_file)
+        .with_resolver(&documents)
+        .build()
+        .try_generate()
+        .unwrap();
+    let file: syn::File = syn::parse2(tokens).unwrap();
+
+    assert_eq!(field_type(&file, "Root", "owner"), ty("Option<Person>"));
+    assert_eq!(field_type(&file, "Person", "name"), ty("Option<String>"));
+    assert_eq!(
+        field_type(&file, "Root", "k"),
+        ty("Option<MultiplePropertyTypesK>")
+    );
+    assert_eq!(
+        field_type(&file, "MultiplePropertyTypesK", "l"),
+        ty("Option<Vec<i64>>")
+    );
+}
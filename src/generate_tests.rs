@@ -1,8 +1,9 @@
 //! Generate test cases from the JSON Schema Test Suite.
 
 use inflector::Inflector;
+use schemafy_lib::{Resolver, Uri};
 use serde::{Deserialize, Serialize};
-use std::{error::Error, ffi::OsStr, fs, path::PathBuf, process::Command};
+use std::{error::Error, ffi::OsStr, fs, io, path::PathBuf, process::Command};
 
 // Each test has a description, schema, and a list of tests. Each of
 // those tests has a description, some data, and a `valid` field which
@@ -12,6 +13,22 @@ schemafy::schemafy!(
     "tests/JSON-Schema-Test-Suite/test-schema.json"
 );
 
+/// Serves the remote schemas of the test suite, which the tests expect at
+/// `http://localhost:1234/`.
+struct Remotes;
+
+impl Resolver for Remotes {
+    fn resolve(&self, uri: &Uri) -> io::Result<serde_json::Value> {
+        let uri = uri.to_string();
+        let path = uri
+            .strip_prefix("http://localhost:1234/")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, uri.clone()))?;
+        let json =
+            fs::read_to_string(PathBuf::from("tests/JSON-Schema-Test-Suite/remotes").join(path))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let test_suite_dir = PathBuf::from("tests/test_suite");
     let schemas_dir = test_suite_dir.join("schemas");
@@ -58,6 +75,8 @@ fn main() -> Result<(), Box<dyn Error>> {
             let schema = schemafy_lib::Generator::builder()
                 .with_root_name_str("Schema")
                 .with_input_file(&schemas_dir.join(schema_name))
+                .with_resolver(&Remotes)
+                .with_validating_newtypes(true)
                 .build()
                 .generate();
 
@@ -97,13 +116,13 @@ mod _{}_{} {{
                 };
 
                 test_file.push_str(&format!(
-                    r##"
+                    r###"
     #[test]
     fn r#{}() {{
-        let data = r#"{}"#;
+        let data = r##"{}"##;
         {}
     }}
-"##,
+"###,
                     test_name, test.data, assertion
                 ));
             }
@@ -149,22 +168,18 @@ fn is_blacklisted(test_group: &str, index: usize) -> bool {
         "dependencies" => &[0, 1, 2, 3],
         "enum" => &[0, 1, 3, 4, 5, 6, 7],
         "items" => &[0, 1, 2],
-        "max_items" => &[0],
-        "max_length" => &[0],
-        "max_properties" => &[0],
-        "maximum" => &[0, 1, 2],
-        "min_items" => &[0],
-        "min_length" => &[0],
-        "min_properties" => &[0],
-        "minimum" => &[0, 1, 2, 3],
-        "multiple_of" => &[0, 1, 2],
         "not" => &[0, 1, 2, 3],
         "one_of" => &[0, 1, 2, 3, 4],
-        "pattern" => &[0],
         "pattern_properties" => &[0, 1, 2],
         "properties" => &[0, 1, 2],
-        "ref" => &[0, 1, 2, 3, 6, 8, 9, 10, 11, 12],
-        "ref_remote" => &[0, 1, 2, 3, 4, 5, 6],
+        // 0: the root schema becomes a struct, which rejects non-object instances
+        // 2, 10, 11: schemas without a `type` become `serde_json::Value`
+        // 3: the pointer goes through members that are not keywords, which `Schema` drops
+        // 6: the draft 4 meta-schema is not one of the remotes
+        "ref" => &[0, 2, 3, 6, 10, 11],
+        // 1, 2: `subSchemas.json#/integer` is not a keyword, so it cannot be resolved
+        // 3: the `items` schema has no `type` and becomes `serde_json::Value`
+        "ref_remote" => &[1, 2, 3],
         "required" => &[0, 2],
         "type" => &[6, 7, 9, 10],
         "unique_items" => &[0, 1, 2],
@@ -3,11 +3,13 @@
 // #![feature(external_doc)]
 // #![doc(include = "../README.md")]
 
-//! This is a Rust crate which can take a [json schema (draft
-//! 4)](http://json-schema.org/) and generate Rust types which are
+//! This is a Rust crate which can take a [json schema (draft 4 up
+//! to 2020-12)](http://json-schema.org/) and generate Rust types which are
 //! serializable with [serde](https://serde.rs/). No checking such as
-//! `min_value` are done but instead only the structure of the schema
-//! is followed as closely as possible.
+//! `min_value` are done by default but instead only the structure of
+//! the schema is followed as closely as possible. Such constraints can
+//! be checked by opting into generated `validate` methods, see
+//! [`schemafy!`].
 //!
 //! As a schema could be arbitrarily complex this crate makes no
 //! guarantee that it can generate good types or even any types at all
@@ -54,6 +56,11 @@
 /// If the `root` parameter is supplied, then a type will be
 /// generated from the root of the schema.
 ///
+/// References to other schema files, such as `"$ref":
+/// "common.json#/definitions/Address"`, are resolved relative to the file
+/// containing the reference. A referenced schema whose name is already taken
+/// by another schema is named after its file as well, `CommonAddress`.
+///
 /// ```rust
 /// extern crate serde;
 /// extern crate schemafy_core;
@@ -72,39 +79,235 @@
 ///     Ok(())
 /// }
 /// ```
+///
+/// Schemas of draft 6 and later are supported as well, except for the
+/// keywords which restrict the values of a type without changing its shape:
+/// `contains`, `propertyNames` and `if` are ignored, neither the generated
+/// types nor `validate` check them. The properties of `then` and `else`
+/// become optional fields, as either of them may apply.
+///
+/// The schema file and the files it references are tracked, so the code is
+/// regenerated whenever one of them changes.
+///
+/// # Options
+///
+/// With `validate: true` the generated types also check the constraints
+/// which serde does not enforce, such as `minimum`, `maxLength` or
+/// `pattern`. Structs get a `validate` method reporting every violated
+/// constraint by the JSON pointer of the value.
+///
+/// ```rust
+/// extern crate serde;
+/// extern crate schemafy_core;
+/// extern crate serde_json;
+///
+/// use serde::{Serialize, Deserialize};
+///
+/// schemafy::schemafy!(
+///     root: Limits
+///     validate: true
+///     "tests/validate.json"
+/// );
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let limits: Limits = serde_json::from_str(r#"{ "name": "", "size": 3 }"#)?;
+///     let errors = limits.validate().unwrap_err();
+///     assert_eq!(errors.iter().next().unwrap().path(), "/name");
+///     Ok(())
+/// }
+/// ```
+///
+/// With `validating_newtypes: true` numbers, strings, arrays and maps with
+/// such constraints become newtypes instead, which reject invalid values
+/// while deserializing.
+///
+/// Properties which an object does not declare are kept in a flattened
+/// `extra` map, typed after its `additionalProperties`. With
+/// `extra_properties: true` they are also kept, as `serde_json::Value`s, if
+/// `additionalProperties` is `true` or missing. The map is typed after the
+/// `patternProperties` of the object as well, through an untagged enum if
+/// there are several types. Without additional properties, the names of the
+/// properties are checked against the patterns by `validate`, or while
+/// deserializing with `validating_newtypes: true`.
+///
+/// With `unsigned_integers: true`, integers which only have a non-negative
+/// `minimum` become `u64` instead of `i64`.
+///
+/// `formats: { "ipv4": "std::net::Ipv4Addr" }` maps strings of a format to a
+/// type, see [Formats](#formats), and `types: { "/properties/amount":
+/// "serde_json::Number" }` gives any schema a type of your own by its JSON
+/// pointer, for instance for numbers which overflow `i64` and `f64` (with the
+/// `arbitrary_precision` feature of `schemafy_core`).
+///
+/// # Features
+///
+/// `validate: true` and `validating_newtypes: true` need the `validate`
+/// feature of `schemafy_core`, enabled on the dependency on `schemafy_core` of
+/// the crate using the macro.
+///
+/// The features `chrono`, `uuid` and `url` map `date-time`, `date` and `time`
+/// to `chrono` types, `uuid` to `uuid::Uuid` and `uri` to `url::Url`, as
+/// re-exported by `schemafy_core` with the feature of the same name, which
+/// then needs to be enabled on `schemafy_core` as well. The `formats`
+/// feature maps `ipv4` and `ipv6` to `std::net` addresses, `email` and
+/// `hostname` to the validated types of `schemafy_core::format` and `byte` and
+/// `binary` to base64 encoded bytes.
+///
+/// # Formats
+///
+/// Strings with a `format` can be generated as stronger types than `String`,
+/// either through the features above or by mapping the format to a type of
+/// your own, which deserializes from and serializes to the string:
+///
+/// ```rust
+/// extern crate serde;
+/// extern crate schemafy_core;
+/// extern crate serde_json;
+///
+/// use serde::{Serialize, Deserialize};
+///
+/// schemafy::schemafy!(
+///     root: Formats
+///     formats: { "ipv4": "std::net::Ipv4Addr", "email": "schemafy_core::format::Email" }
+///     "tests/formats.json"
+/// );
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let formats: Formats = serde_json::from_str(r#"{ "address": "127.0.0.1" }"#)?;
+///     assert!(formats.address.unwrap().is_loopback());
+///     Ok(())
+/// }
+/// ```
+///
+/// Numbers encoded as strings, such as `{"type": "string", "format":
+/// "int64"}`, become `schemafy_core::number::StringEncoded` numbers and
+/// numbers of the `decimal` format become `schemafy_core::number::Decimal`.
+///
+/// Integers get the type which their OpenAPI `format`, such as `int32` or
+/// `uint64`, names, or else the smallest type holding every value between
+/// their `minimum` and `maximum`. Numbers of the `float` format become `f32`.
+///
+/// # Errors
+///
+/// Problems with the schema, such as a missing file, invalid JSON or a
+/// `$ref` which cannot be resolved, are reported as compile errors naming
+/// the file, the line and column and (for schemas within the file) the JSON
+/// pointer of the problem.
+///
+/// ```compile_fail
+/// schemafy::schemafy!("tests/does-not-exist.json");
+/// ```
 #[proc_macro]
 pub fn schemafy(tokens: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let def = syn::parse_macro_input!(tokens as Def);
-    let root_name = def.root;
+    let root_name = def.root.as_ref().map(|root| root.to_string());
     let input_file = def.input_file.value();
-    schemafy_lib::Generator::builder()
+    let mut builder = schemafy_lib::Generator::builder()
         .with_root_name(root_name)
         .with_input_file(&input_file)
-        .build()
-        .generate()
-        .into()
+        .with_track_files(true)
+        .with_validate(def.validate)
+        .with_validating_newtypes(def.validating_newtypes)
+        .with_unsigned_integers(def.unsigned_integers)
+        .with_extra_properties(def.extra_properties);
+    for (format, typ) in &def.formats {
+        builder = builder.with_format(&format.value(), &typ.value());
+    }
+    for (pointer, typ) in &def.types {
+        builder = builder.with_type(&pointer.value(), &typ.value());
+    }
+    let generator = builder.build();
+    match generator.try_generate() {
+        Ok(tokens) => tokens.into(),
+        Err(err) => {
+            // Errors in the root schema itself concern the root type, everything
+            // else is best pointed out at the file
+            let span = match def.root {
+                Some(ref root)
+                    if err.document().is_some()
+                        && err.pointer().is_empty()
+                        && err.position().is_none() =>
+                {
+                    root.span()
+                }
+                _ => def.input_file.span(),
+            };
+            syn::Error::new(span, err).to_compile_error().into()
+        }
+    }
 }
 
 struct Def {
-    root: Option<String>,
+    root: Option<syn::Ident>,
+    validate: bool,
+    validating_newtypes: bool,
+    unsigned_integers: bool,
+    extra_properties: bool,
+    formats: Vec<(syn::LitStr, syn::LitStr)>,
+    types: Vec<(syn::LitStr, syn::LitStr)>,
     input_file: syn::LitStr,
 }
 
 impl syn::parse::Parse for Def {
     fn parse(input: syn::parse::ParseStream<'_>) -> syn::Result<Self> {
-        let root = if input.peek(syn::Ident) {
-            let root_ident: syn::Ident = input.parse()?;
-            if root_ident != "root" {
-                return Err(syn::Error::new(root_ident.span(), "Expected `root`"));
-            }
+        let mut root = None;
+        let mut validate = false;
+        let mut validating_newtypes = false;
+        let mut unsigned_integers = false;
+        let mut extra_properties = false;
+        let mut formats = Vec::new();
+        let mut types = Vec::new();
+        while input.peek(syn::Ident) {
+            let option: syn::Ident = input.parse()?;
             input.parse::<syn::Token![:]>()?;
-            Some(input.parse::<syn::Ident>()?.to_string())
-        } else {
-            None
-        };
+            if option == "root" {
+                root = Some(input.parse::<syn::Ident>()?);
+            } else if option == "validate" {
+                validate = input.parse::<syn::LitBool>()?.value;
+            } else if option == "validating_newtypes" {
+                validating_newtypes = input.parse::<syn::LitBool>()?.value;
+            } else if option == "unsigned_integers" {
+                unsigned_integers = input.parse::<syn::LitBool>()?.value;
+            } else if option == "extra_properties" {
+                extra_properties = input.parse::<syn::LitBool>()?.value;
+            } else if option == "formats" {
+                formats = parse_string_map(input)?;
+            } else if option == "types" {
+                types = parse_string_map(input)?;
+            } else {
+                return Err(syn::Error::new(
+                    option.span(),
+                    "Expected `root`, `validate`, `validating_newtypes`, `unsigned_integers`, `extra_properties`, `formats` or `types`",
+                ));
+            }
+        }
         Ok(Def {
             root,
+            validate,
+            validating_newtypes,
+            unsigned_integers,
+            extra_properties,
+            formats,
+            types,
             input_file: input.parse()?,
         })
     }
 }
+
+/// Parses `{ "key": "value", ... }`.
+fn parse_string_map(
+    input: syn::parse::ParseStream<'_>,
+) -> syn::Result<Vec<(syn::LitStr, syn::LitStr)>> {
+    let content;
+    syn::braced!(content in input);
+    let mut entries = Vec::new();
+    while !content.is_empty() {
+        let key = content.parse::<syn::LitStr>()?;
+        content.parse::<syn::Token![:]>()?;
+        entries.push((key, content.parse::<syn::LitStr>()?));
+        if !content.is_empty() {
+            content.parse::<syn::Token![,]>()?;
+        }
+    }
+    Ok(entries)
+}
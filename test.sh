@@ -4,3 +4,4 @@ cargo build --features internal-regenerate
 cargo run --bin generate-tests --features="generate-tests"
 cargo fmt --all
 cargo test --all 
+cargo test -p schemafy_lib --features validator
@@ -1,3 +1,6 @@
+// The existing tests predate some of the lints of newer clippy versions
+#![allow(clippy::get_first)]
+
 use serde_derive::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
@@ -179,6 +182,155 @@ fn one_of_parsing() {
     assert!(serde_json::from_str::<OneOfSchema>(r#"{"foo":3}"#).is_err());
 }
 
+schemafy::schemafy!(
+    root: OneOfTagged
+    "tests/one-of-tagged.json"
+);
+
+#[test]
+fn one_of_tagged() {
+    let t: OneOfTagged =
+        serde_json::from_str(r#"{"message":{"type":"request","seq":1,"command":"launch"}}"#)
+            .unwrap();
+    assert_eq!(
+        t.message,
+        OneOfTaggedMessage::Request(Box::new(Command {
+            seq: 1,
+            command: "launch".to_string(),
+            type_: CommandType,
+        }))
+    );
+
+    let t: OneOfTagged = serde_json::from_str(r#"{"message":{"type":"heartbeat"}}"#).unwrap();
+    assert_eq!(t.message, OneOfTaggedMessage::Heartbeat);
+
+    let message = OneOfTaggedMessage::Event(Box::new(Notification {
+        seq: 2,
+        event: "stopped".to_string(),
+        type_: NotificationType,
+    }));
+    assert_eq!(
+        serde_json::to_value(&message).unwrap(),
+        serde_json::json!({"type": "event", "seq": 2, "event": "stopped"})
+    );
+
+    // The tag selects the variant, so the error is about the missing field
+    let err = serde_json::from_str::<OneOfTaggedMessage>(r#"{"type":"event","seq":3}"#)
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("missing field `event`"), "{}", err);
+}
+
+schemafy::schemafy!(
+    root: AnyOf
+    "tests/any-of.json"
+);
+
+#[test]
+fn any_of_untagged() {
+    // `integer` is more specific than `number` and is tried first
+    let t: AnyOf = serde_json::from_str(r#"{"amount": 1}"#).unwrap();
+    assert_eq!(t.amount, Some(AnyOfAmount::Variant1(1)));
+    let t: AnyOf = serde_json::from_str(r#"{"amount": 1.5}"#).unwrap();
+    assert_eq!(t.amount, Some(AnyOfAmount::Variant0(1.5)));
+
+    // A `null` branch makes the type optional
+    let t: AnyOf = serde_json::from_str(r#"{"origin": null}"#).unwrap();
+    assert_eq!(t.origin, None);
+    let t: AnyOf = serde_json::from_str(r#"{"origin": {"x": 1, "y": 2}}"#).unwrap();
+    assert_eq!(t.origin, Some(Spot { x: 1.0, y: 2.0 }));
+
+    // `Circle` requires more properties than `Spot`, so it is tried first
+    let t: AnyOf = serde_json::from_str(r#"{"shape": {"x": 1, "y": 2, "radius": 3}}"#).unwrap();
+    assert_eq!(
+        t.shape,
+        Some(AnyOfShape::Variant1(Box::new(Circle {
+            x: 1.0,
+            y: 2.0,
+            radius: 3.0
+        })))
+    );
+    let t: AnyOf = serde_json::from_str(r#"{"shape": "square"}"#).unwrap();
+    assert_eq!(t.shape, Some(AnyOfShape::Variant2("square".to_string())));
+}
+
+schemafy::schemafy!(
+    root: MultiType
+    "tests/multi-type.json"
+);
+
+#[test]
+fn multi_type_enums() {
+    let t: MultiType = serde_json::from_str(
+        r#"{"id": "a", "limit": 10, "arguments": [1], "label": {"text": "b"}}"#,
+    )
+    .unwrap();
+    assert_eq!(t.id, MultiTypeId::String("a".to_string()));
+    assert_eq!(t.limit, Some(MultiTypeLimit::Integer(10)));
+    assert_eq!(
+        t.arguments,
+        Some(MultiTypeArguments::Array(vec![serde_json::json!(1)]))
+    );
+    assert_eq!(
+        t.label,
+        Some(MultiTypeLabel::Object(MultiTypeLabelObject {
+            text: "b".to_string()
+        }))
+    );
+
+    let t: MultiType =
+        serde_json::from_str(r#"{"id": 7, "limit": null, "arguments": 1.5, "label": "c"}"#)
+            .unwrap();
+    assert_eq!(t.id, MultiTypeId::Integer(7));
+    assert_eq!(t.limit, None);
+    assert_eq!(t.arguments, Some(MultiTypeArguments::Number(1.5)));
+    assert_eq!(t.label, Some(MultiTypeLabel::String("c".to_string())));
+}
+
+schemafy::schemafy!(
+    root: Pets
+    "tests/discriminator.json"
+);
+
+#[test]
+fn discriminator_mapping() {
+    let t: Pets = serde_json::from_str(r#"{"pet":{"petType":"kitten","name":"Tom"}}"#).unwrap();
+    assert_eq!(
+        t.pet,
+        PetsPet::Cat(Box::new(Cat {
+            pet_type: "kitten".to_string(),
+            name: "Tom".to_string()
+        }))
+    );
+
+    let t: Pets = serde_json::from_str(r#"{"pet":{"petType":"dog","bark":true}}"#).unwrap();
+    assert_eq!(
+        t.pet,
+        PetsPet::Dog(Box::new(Dog {
+            pet_type: "dog".to_string(),
+            bark: true
+        }))
+    );
+
+    // Without a mapping the name of the schema is the value
+    let pet = PetsPet::Lizard(Box::new(Lizard {
+        pet_type: "Lizard".to_string(),
+        loves_rocks: Some(false),
+    }));
+    assert_eq!(
+        serde_json::to_value(&pet).unwrap(),
+        serde_json::json!({"petType": "Lizard", "lovesRocks": false})
+    );
+    assert_eq!(
+        serde_json::to_value(PetsPet::Cat(Box::new(Cat {
+            pet_type: "cat".to_string(),
+            name: "Tom".to_string()
+        })))
+        .unwrap(),
+        serde_json::json!({"petType": "cat", "name": "Tom"})
+    );
+}
+
 schemafy::schemafy!(
     root: PatternProperties
     "tests/pattern-properties.json"
@@ -193,8 +345,564 @@ fn unknown_fields() {
     // empty struct with additionalProperties: true
     serde_json::from_str::<AnyProperties>(r#"{"zzz": 5}"#).unwrap();
     // empty struct with additionalProperties: false and patternProperties
-    // non-empty
-    serde_json::from_str::<PatternProperties>(r#"{"zzz": 5}"#).unwrap();
+    // non-empty, whose names are only checked by validation
+    serde_json::from_str::<PatternProperties>(r#"{"zzz": {"a": 5}}"#).unwrap();
+    serde_json::from_str::<PatternProperties>(r#"{"zzz": 5}"#).unwrap_err();
     // non-empty struct with additionalProperties unspecified
     serde_json::from_str::<ArrayType>(r#"{"required": [], "zzz": 5}"#).unwrap();
 }
+
+schemafy::schemafy!(
+    root: Draft202012
+    "tests/draft-2020-12.json"
+);
+
+#[test]
+fn draft_2020_12() {
+    let point: Draft202012 = serde_json::from_str(
+        r#"{"kind": "point", "position": [1, 2.5], "label": "origin", "tags": [], "color": "red"}"#,
+    )
+    .unwrap();
+    assert_eq!(point.kind, Draft202012Kind);
+    assert_eq!(point.position, (1.0, 2.5));
+    assert_eq!(point.label, Some(Label));
+    assert_eq!(point.color, Some("red".into()));
+    let _: Option<Vec<serde_json::Value>> = point.tags;
+
+    serde_json::from_str::<Draft202012>(r#"{"kind": "point", "position": [1, 2, 3]}"#).unwrap_err();
+    serde_json::from_str::<Draft202012>(r#"{"kind": "line", "position": [1, 2]}"#).unwrap_err();
+    serde_json::from_str::<Draft202012>(r#"{"kind": "point", "position": [1, 2], "zzz": 5}"#)
+        .unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: Draft07
+    "tests/draft-07.json"
+);
+
+#[test]
+fn draft_07() {
+    let value: Draft07 =
+        serde_json::from_str(r#"{"version": 7, "country": "US", "zip": "12345", "weight": 1.5}"#)
+            .unwrap();
+    assert_eq!(value.version, Draft07Version);
+    assert_eq!(serde_json::to_value(Draft07Version).unwrap(), 7);
+    assert_eq!(value.id, None);
+    assert_eq!(value.password, None);
+    assert_eq!(value.zip, Some("12345".into()));
+    assert_eq!(value.postal_code, None);
+    assert_eq!(value.weight, Some(1.5));
+    let _: Option<Vec<String>> = value.tags;
+    let _: Option<::std::collections::BTreeMap<String, i64>> = value.meta;
+    let _: Option<serde_json::Value> = value.anything;
+}
+
+schemafy::schemafy!(
+    root: ExternalRefs
+    "tests/external-refs/root.json"
+);
+
+#[test]
+fn external_refs() {
+    let value: ExternalRefs = serde_json::from_str(
+        r#"{
+            "home": { "street": "Main Street", "city": "Springfield" },
+            "owner": {
+                "name": "Homer",
+                "address": { "street": "Main Street", "city": "Springfield" },
+                "email": "homer@example.com"
+            }
+        }"#,
+    )
+    .unwrap();
+    let owner: Person = value.owner.unwrap();
+    assert_eq!(owner.address, Some(value.home));
+    let _: Option<Email> = owner.email;
+}
+
+schemafy::schemafy!(
+    root: JsonPointer
+    "tests/json-pointer.json"
+);
+
+#[test]
+fn json_pointer_refs() {
+    let value: JsonPointer = serde_json::from_str(
+        r#"{
+            "address": { "street": "Main Street" },
+            "billing": { "street": "Side Street" },
+            "primaryTag": { "name": "home" },
+            "ratio": 0.5,
+            "count": 3,
+            "size": "large",
+            "first": { "left": 1 },
+            "extra": { "note": "none" }
+        }"#,
+    )
+    .unwrap();
+    let _: Option<JsonPointerAddress> = value.address;
+    let billing: JsonPointerAddress = value.billing.unwrap();
+    assert_eq!(billing.street, Some("Side Street".into()));
+    let tag: JsonPointerTagsItem = value.primary_tag.unwrap();
+    assert_eq!(tag.name, Some("home".into()));
+    let _: Option<UnitsAB> = value.ratio;
+    let _: Option<UnitsMN> = value.count;
+    assert_eq!(value.size, Some(UnitsWithSpace::Large));
+    let first: PairItem0 = value.first.unwrap();
+    assert_eq!(first.left, Some(1));
+    let _: Option<UnitsValue> = value.extra;
+}
+
+schemafy::schemafy!(
+    root: Ids
+    "tests/ids.json"
+);
+
+#[test]
+fn ids_and_anchors() {
+    let value: Ids = serde_json::from_str(
+        r#"{
+            "item": { "name": "chair", "tag": "used" },
+            "point": { "x": 1, "y": 2 },
+            "legacy": 3,
+            "folder": { "file": "notes.txt" }
+        }"#,
+    )
+    .unwrap();
+    let item: Item = value.item.unwrap();
+    assert_eq!(item.tag, Some(Tag::Used));
+    let point: Point = value.point.unwrap();
+    assert_eq!(point.x, Some(1.0));
+    let _: Option<Legacy> = value.legacy;
+    let folder: Folder = value.folder.unwrap();
+    let _: Option<Name> = folder.file;
+}
+
+schemafy::schemafy!(
+    root: Constants
+    "tests/constants.json"
+);
+
+#[test]
+fn constant_marker_types() {
+    let json = r#"{"kind":"request","reserved":null,"version":2}"#;
+    let t: Constants = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        t,
+        Constants {
+            kind: ConstantsKind,
+            version: ConstantsVersion,
+            reserved: ConstantsReserved,
+        }
+    );
+    assert_eq!(serde_json::to_string(&t).unwrap(), json);
+
+    assert!(serde_json::from_str::<ConstantsKind>(r#""response""#).is_err());
+    assert!(serde_json::from_str::<ConstantsVersion>("3").is_err());
+    assert!(serde_json::from_str::<ConstantsReserved>("0").is_err());
+}
+
+schemafy::schemafy!(
+    root: EnumValues
+    "tests/enum-values.json"
+);
+
+#[test]
+fn enum_values_without_names() {
+    let t: EnumValues =
+        serde_json::from_str(r#"{"priority": -1, "enabled": true, "level": "auto"}"#).unwrap();
+    assert_eq!(t.priority, Priority::VMinus1);
+    assert_eq!(t.enabled, Some(true));
+    assert_eq!(t.level, Level::Auto(LevelAuto));
+
+    let t: EnumValues =
+        serde_json::from_str(r#"{"priority": 2, "enabled": null, "level": 0.5}"#).unwrap();
+    assert_eq!(t.priority, Priority::V2);
+    assert_eq!(t.enabled, None);
+    assert_eq!(t.level, Level::V0_5(LevelV0_5));
+    assert_eq!(
+        serde_json::to_value(&t).unwrap(),
+        serde_json::json!({"priority": 2, "enabled": null, "level": 0.5})
+    );
+
+    let t: EnumValues =
+        serde_json::from_str(r#"{"priority": 0, "enabled": true, "level": false}"#).unwrap();
+    assert_eq!(t.level, Level::False(LevelFalse));
+    assert!(serde_json::from_str::<Level>("2").is_err());
+    assert!(serde_json::from_str::<Priority>("3").is_err());
+}
+
+schemafy::schemafy!(
+    root: InlineEnum
+    "tests/inline-enum.json"
+);
+
+#[test]
+fn inline_enums() {
+    let t: InlineEnum =
+        serde_json::from_str(r#"{"direction": "south", "colors": ["green"]}"#).unwrap();
+    assert_eq!(t.direction, InlineEnumDirection::South);
+    assert_eq!(t.colors, Some(vec![InlineEnumItemColors::Green]));
+    assert!(serde_json::from_str::<InlineEnum>(r#"{"direction": "east"}"#).is_err());
+
+    let t: InlineEnum =
+        serde_json::from_str(r#"{"direction": "north", "priority": 2, "width": 100}"#).unwrap();
+    assert_eq!(t.priority, Some(InlineEnumPriority::V2));
+    assert_eq!(t.width, Some(InlineEnumWidth::V100(InlineEnumWidthV100)));
+    assert_eq!(
+        serde_json::to_value(&t).unwrap(),
+        serde_json::json!({"direction": "north", "priority": 2, "width": 100})
+    );
+    let t: InlineEnum = serde_json::from_str(r#"{"direction": "north", "width": "auto"}"#).unwrap();
+    assert_eq!(t.width, Some(InlineEnumWidth::Auto(InlineEnumWidthAuto)));
+    assert!(
+        serde_json::from_str::<InlineEnum>(r#"{"direction": "north", "priority": 4}"#).is_err()
+    );
+    assert!(serde_json::from_str::<InlineEnum>(r#"{"direction": "north", "width": 50}"#).is_err());
+}
+
+schemafy::schemafy!(
+    root: Limits
+    validate: true
+    "tests/validate.json"
+);
+
+#[test]
+fn validate_constraints() {
+    let limits: Limits = serde_json::from_str(
+        r#"{"name": "abc", "size": 10, "ratio": 0.5, "tags": ["a", "b"], "quotas": {"a": 0},
+            "parts": [{"weight": 100}]}"#,
+    )
+    .unwrap();
+    assert_eq!(limits.validate(), Ok(()));
+
+    let limits: Limits = serde_json::from_str(
+        r#"{"name": "ABCDEFGHI", "size": 0, "ratio": 0.3, "tags": ["a", "a", "long", "b"],
+            "quotas": {"a": -1, "b/c": 1, "d": 2}, "parts": [{}, {"weight": 101}]}"#,
+    )
+    .unwrap();
+    let errors = limits.validate().unwrap_err();
+    let errors = errors
+        .iter()
+        .map(|error| (error.path(), error.keyword()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        [
+            ("/name", "maxLength"),
+            ("/name", "pattern"),
+            ("/parts/0", "minProperties"),
+            ("/parts/1/weight", "maximum"),
+            ("/quotas", "maxProperties"),
+            ("/quotas/a", "minimum"),
+            ("/ratio", "multipleOf"),
+            ("/size", "minimum"),
+            ("/tags", "maxItems"),
+            ("/tags", "uniqueItems"),
+            ("/tags/2", "maxLength"),
+        ]
+    );
+}
+
+schemafy::schemafy!(
+    root: Newtypes
+    validating_newtypes: true
+    "tests/newtypes.json"
+);
+
+#[test]
+fn validating_newtypes() {
+    use std::convert::TryFrom;
+
+    let t: Newtypes =
+        serde_json::from_str(r#"{"code": "ABC", "port": 80, "scores": [1.5], "note": 7}"#).unwrap();
+    assert_eq!(*t.code, "ABC");
+    assert_eq!(u16::from(t.port.clone()), 80);
+    assert_eq!(t.scores.as_ref().unwrap().len(), 1);
+    assert_eq!(
+        serde_json::to_string(&t).unwrap(),
+        r#"{"code":"ABC","note":7,"port":80,"scores":[1.5]}"#
+    );
+
+    let err = serde_json::from_str::<Newtypes>(r#"{"code": "abc", "port": 80}"#).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with(r#""abc" does not match `^[A-Z]{3}$`"#));
+    serde_json::from_str::<Newtypes>(r#"{"code": "ABC", "port": 0}"#).unwrap_err();
+    serde_json::from_str::<Newtypes>(r#"{"code": "ABC", "port": 80, "scores": [1, 2, 3]}"#)
+        .unwrap_err();
+    serde_json::from_str::<Newtypes>(r#"{"code": "ABC", "port": 80, "scores": [-1]}"#).unwrap_err();
+    serde_json::from_str::<Newtypes>(r#"{"code": "ABC", "port": 80, "note": "abc"}"#).unwrap_err();
+
+    assert!(Port::try_from(65535).is_ok());
+    assert_eq!(
+        Port::try_from(0).unwrap_err().to_string(),
+        "0 is not at least 1"
+    );
+}
+
+schemafy::schemafy!(
+    root: Defaults
+    "tests/defaults.json"
+);
+
+#[test]
+fn default_values() {
+    let defaults = Defaults::default();
+    assert_eq!(defaults.port, 8080);
+    assert_eq!(defaults.host, "localhost");
+    assert!(defaults.verbose);
+    assert_eq!(defaults.ratio, 0.5);
+    assert_eq!(defaults.level, LogLevel::Info);
+    assert_eq!(defaults.retries, 3);
+    assert_eq!(defaults.tags, ["a", "b"]);
+    assert_eq!(defaults.name, None);
+
+    let t: Defaults = serde_json::from_str("{}").unwrap();
+    assert_eq!(t, defaults);
+
+    let t: Defaults = serde_json::from_str(r#"{"port": 80, "level": "error"}"#).unwrap();
+    assert_eq!(t.port, 80);
+    assert_eq!(t.level, LogLevel::Error);
+    assert_eq!(t.host, "localhost");
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize, Serialize)]
+pub struct FormatsId(String);
+
+schemafy::schemafy!(
+    root: Formats
+    validate: true
+    formats: {
+        "ipv4": "std::net::Ipv4Addr",
+        "email": "schemafy_core::format::Email",
+        "hostname": "schemafy_core::format::Hostname",
+        "byte": "schemafy_core::format::Base64",
+        "uuid": "FormatsId",
+        "date-time": "String",
+    }
+    "tests/formats.json"
+);
+
+#[test]
+fn mapped_formats() {
+    let t: Formats = serde_json::from_str(
+        r#"{
+            "address": "127.0.0.1",
+            "contact": "me@example.com",
+            "hosts": ["localhost"],
+            "id": "0000",
+            "data": "Zm9v",
+            "created": "2021-01-01T00:00:00Z"
+        }"#,
+    )
+    .unwrap();
+    assert!(t.address.unwrap().is_loopback());
+    assert_eq!(&**t.contact.as_ref().unwrap(), "me@example.com");
+    assert_eq!(t.data.as_ref().unwrap().0, b"foo");
+    assert_eq!(t.id, Some(FormatsId("0000".into())));
+    assert_eq!(t.created.as_deref(), Some("2021-01-01T00:00:00Z"));
+
+    // The constraints of strings only apply to unmapped formats
+    let errors = t.validate().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors.iter().next().unwrap().path(), "/created");
+
+    serde_json::from_str::<Formats>(r#"{"address": "localhost"}"#).unwrap_err();
+    serde_json::from_str::<Formats>(r#"{"contact": "nobody"}"#).unwrap_err();
+    serde_json::from_str::<Formats>(r#"{"hosts": ["-"]}"#).unwrap_err();
+    serde_json::from_str::<Formats>(r#"{"data": "!"}"#).unwrap_err();
+}
+
+#[cfg(all(feature = "chrono", feature = "url", feature = "uuid"))]
+mod feature_formats {
+    use serde_derive::{Deserialize, Serialize};
+
+    schemafy::schemafy!(
+        root: FeatureFormats
+        "tests/feature-formats.json"
+    );
+
+    #[test]
+    fn formats_of_features() {
+        let t: FeatureFormats = serde_json::from_str(
+            r#"{
+                "created": "2021-01-01T00:00:00+02:00",
+                "day": "2021-01-01",
+                "alarm": "07:30:00",
+                "id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+                "homepage": "https://example.com/"
+            }"#,
+        )
+        .unwrap();
+        let _: schemafy_core::chrono::DateTime<schemafy_core::chrono::FixedOffset> =
+            t.created.unwrap();
+        let _: schemafy_core::chrono::NaiveDate = t.day.unwrap();
+        let _: schemafy_core::chrono::NaiveTime = t.alarm.unwrap();
+        let _: schemafy_core::uuid::Uuid = t.id.unwrap();
+        assert_eq!(t.homepage.unwrap().host_str(), Some("example.com"));
+    }
+}
+
+schemafy::schemafy!(
+    root: Widths
+    "tests/widths.json"
+);
+
+schemafy::schemafy!(
+    root: UnsignedWidths
+    unsigned_integers: true
+    "tests/widths.json"
+);
+
+#[test]
+fn integer_and_float_widths() {
+    let json = r#"{
+        "byte": 255,
+        "offset": -100,
+        "below": 255,
+        "count": -5,
+        "big": 18446744073709551615,
+        "ratio": 0.5,
+        "size": 7,
+        "samples": [0, 65535]
+    }"#;
+    let t: Widths = serde_json::from_str(json).unwrap();
+    let _: (u8, i8, u8, i32, u64, f32, i64, Vec<u16>) = (
+        t.byte, t.offset, t.below, t.count, t.big, t.ratio, t.size, t.samples,
+    );
+    assert_eq!(t.big, u64::MAX);
+
+    let t: UnsignedWidths = serde_json::from_str(json).unwrap();
+    let _: u64 = t.size;
+
+    serde_json::from_str::<Widths>(&json.replace("255,", "256,")).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: Numbers
+    validate: true
+    types: {
+        "/properties/huge": "serde_json::Number",
+        "/definitions/NumbersTotal": "serde_json::Number",
+    }
+    "tests/numbers.json"
+);
+
+#[test]
+fn encoded_numbers() {
+    use schemafy_core::number::{Decimal, StringEncoded};
+
+    let json = r#"{
+        "id": "9007199254740993",
+        "ids": ["1", "4294967295"],
+        "price": "12.50",
+        "amount": 0.10,
+        "huge": 18446744073709551615,
+        "total": -1
+    }"#;
+    let t: Numbers = serde_json::from_str(json).unwrap();
+    assert_eq!(t.id, StringEncoded(9007199254740993i64));
+    assert_eq!(t.ids.as_ref().unwrap()[1], StringEncoded(u32::MAX));
+    assert_eq!(t.price.as_ref().unwrap().as_str(), "12.50");
+    let _: &Decimal = t.amount.as_ref().unwrap();
+    assert_eq!(t.huge.as_ref().unwrap().as_u64(), Some(u64::MAX));
+    let _: &NumbersTotal = t.total.as_ref().unwrap();
+    assert_eq!(
+        serde_json::to_value(&t).unwrap()["id"],
+        serde_json::json!("9007199254740993")
+    );
+
+    // The constraints of strings and numbers do not apply to these types
+    assert!(t.validate().is_ok());
+
+    serde_json::from_str::<Numbers>(r#"{"id": 1}"#).unwrap_err();
+    serde_json::from_str::<Numbers>(r#"{"id": "1", "ids": ["-1"]}"#).unwrap_err();
+}
+
+schemafy::schemafy!(
+    root: ExtraProperties
+    validate: true
+    extra_properties: true
+    "tests/extra-properties.json"
+);
+
+#[test]
+fn extra_properties() {
+    let json = r#"{"name":"a","x":1,"y":2}"#;
+    let t: ExtraProperties = serde_json::from_str(json).unwrap();
+    assert_eq!(t.name.as_deref(), Some("a"));
+    assert_eq!(t.extra["x"], 1);
+    assert_eq!(t.extra.len(), 2);
+    assert_eq!(serde_json::to_string(&t).unwrap(), json);
+    assert!(t.validate().is_ok());
+    serde_json::from_str::<ExtraProperties>(r#"{"x": "a"}"#).unwrap_err();
+
+    let t: ExtraProperties = serde_json::from_str(r#"{"x": -1, "y": 2, "z": 3, "w": 4}"#).unwrap();
+    let errors = t.validate().unwrap_err();
+    assert_eq!(
+        errors.iter().map(|e| e.path()).collect::<Vec<_>>(),
+        ["", "/x"]
+    );
+
+    let open: ExtraOpen = serde_json::from_str(r#"{"id": "a", "tags": [true]}"#).unwrap();
+    assert_eq!(open.extra["tags"], serde_json::json!([true]));
+
+    let inline: ExtraInline = serde_json::from_str(r#"{"extra": "a", "b": {"size": 1}}"#).unwrap();
+    assert_eq!(inline.extra.as_deref(), Some("a"));
+    let b: &ExtraInlineExtra = &inline.extra_["b"];
+    assert_eq!(b.size, Some(1));
+
+    let closed = ExtraClosed { id: None };
+    assert_eq!(serde_json::to_string(&closed).unwrap(), "{}");
+}
+
+schemafy::schemafy!(
+    root: PatternMap
+    validate: true
+    "tests/pattern-map.json"
+);
+
+schemafy::schemafy!(
+    root: PatternKeys
+    validating_newtypes: true
+    "tests/pattern-keys.json"
+);
+
+#[test]
+fn pattern_properties() {
+    let json = r#"{"labels":{"en":"abc"},"name":"a","nested":{"ab":{"b":1}},"open":{"n1":1.5,"z":[]},"12":3,"x-a":"b"}"#;
+    let t: PatternMap = serde_json::from_str(json).unwrap();
+    assert_eq!(t.extra["12"], PatternMapExtra::Pattern0(3));
+    assert_eq!(t.extra["x-a"], PatternMapExtra::Pattern1("b".into()));
+    let nested: &PatternMapNestedExtra = &t.nested.as_ref().unwrap().extra["ab"];
+    assert_eq!(nested.b, Some(1));
+    assert_eq!(t.labels.as_ref().unwrap().extra["en"], "abc");
+    let open = &t.open.as_ref().unwrap().extra;
+    assert_eq!(open["n1"], OpenExtra::Pattern0(1.5));
+    assert_eq!(open["z"], OpenExtra::Additional(serde_json::json!([])));
+    assert_eq!(serde_json::to_string(&t).unwrap(), json);
+    assert!(t.validate().is_ok());
+
+    let t: PatternMap =
+        serde_json::from_str(r#"{"y": 1, "labels": {"en": "abcd", "EN": "a"}}"#).unwrap();
+    let errors = t.validate().unwrap_err();
+    assert_eq!(
+        errors
+            .iter()
+            .map(|error| (error.path(), error.keyword()))
+            .collect::<Vec<_>>(),
+        [
+            ("/labels/EN", "additionalProperties"),
+            ("/labels/en", "maxLength"),
+            ("/y", "additionalProperties"),
+        ]
+    );
+
+    let t: PatternKeys = serde_json::from_str(r#"{"id": "a", "x-b": "c"}"#).unwrap();
+    assert_eq!(t.extra["x-b"], "c");
+    let err = serde_json::from_str::<PatternKeys>(r#"{"id": "a", "y": "c"}"#).unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with(r#"/y: "y" matches none of the patterns of the properties"#));
+}